@@ -19,11 +19,14 @@ impl Plugin for ActionsPlugin {
     }
 }
 
+/// Systems in this set write the [`Actions`] that the next fixed gameplay step moves the bees with
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InputSet;
+
 #[derive(Default, Resource)]
 pub struct Actions {
     pub p1_movement: Vec2,
     pub p1_bee_movement: Vec2,
-    pub flower_gotten: bool,
     pub input_device: InputDevice,
 }
 
@@ -0,0 +1,266 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::bees::QueenBee;
+use crate::events::WorkerBeeDied;
+use crate::loading::{AnimationAssets, TextureAssets};
+use crate::ron_asset::RonAssetPlugin;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct AnimationPlugin;
+
+/// This plugin plays texture atlas animations on every entity with a [`SpriteAnimation`].
+/// Frame ranges and timings of each clip live in `assets/animations.anim.ron`. Moving entities
+/// pick their clip from their speed, entities marked with [`Dying`] play their death clip once.
+impl Plugin for AnimationPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_plugins(RonAssetPlugin::<AnimationLibrary>::new(&["anim.ron"]))
+            .add_systems(OnEnter(GameState::GameOver), kill_queen)
+            .add_systems(
+                Update,
+                (
+                    spawn_corpses,
+                    select_animation,
+                    animate_sprites,
+                    despawn_finished,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::GameOver))),
+            );
+    }
+}
+
+/// Above this speed bees flap their wings
+const FLAP_SPEED: f32 = 20.0;
+/// Above this speed bees sweep their wings back
+const DASH_SPEED: f32 = 250.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum AnimationSheet {
+    Queen,
+    Worker,
+    Flower,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum AnimationKind {
+    Idle,
+    Flap,
+    Dash,
+    Death,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct AnimationClip {
+    pub first: usize,
+    /// Inclusive
+    pub last: usize,
+    pub fps: f32,
+    pub looping: bool,
+}
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct AnimationLibrary {
+    pub sheets: HashMap<AnimationSheet, HashMap<AnimationKind, AnimationClip>>,
+}
+
+impl AnimationLibrary {
+    /// Falls back to the idle clip for animations a sheet doesn't have
+    pub fn clip(
+        &self,
+        sheet: AnimationSheet,
+        kind: AnimationKind,
+    ) -> Option<AnimationClip> {
+        let clips = self.sheets.get(&sheet)?;
+        clips
+            .get(&kind)
+            .or_else(|| clips.get(&AnimationKind::Idle))
+            .copied()
+    }
+}
+
+#[derive(Component)]
+pub struct SpriteAnimation {
+    pub sheet: AnimationSheet,
+    pub kind: AnimationKind,
+    frame: usize,
+    timer: Timer,
+    finished: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(sheet: AnimationSheet) -> Self {
+        SpriteAnimation {
+            sheet,
+            kind: AnimationKind::Idle,
+            frame: 0,
+            timer: Timer::default(),
+            finished: false,
+        }
+    }
+
+    fn play(
+        &mut self,
+        kind: AnimationKind,
+    ) {
+        if self.kind != kind {
+            self.kind = kind;
+            self.frame = 0;
+            self.timer.reset();
+            self.finished = false;
+        }
+    }
+}
+
+/// Plays the death clip and keeps the entity from picking another animation
+#[derive(Component)]
+pub struct Dying;
+
+/// Despawns the entity once its animation has played to the end
+#[derive(Component)]
+pub struct DespawnWhenFinished;
+
+/// Leaves a dying copy of a bee behind, for bees that are despawned right away
+fn spawn_corpse(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    sheet: AnimationSheet,
+    transform: Transform,
+) {
+    let texture_atlas = match sheet {
+        AnimationSheet::Queen => textures.queen.clone(),
+        AnimationSheet::Worker => textures.bee.clone(),
+        AnimationSheet::Flower => textures.flower.clone(),
+    };
+    let mut animation = SpriteAnimation::new(sheet);
+    animation.play(AnimationKind::Death);
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas,
+            transform,
+            ..default()
+        },
+        animation,
+        Dying,
+        DespawnWhenFinished,
+        RoundEntity,
+    ));
+}
+
+fn spawn_corpses(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    mut died_events: EventReader<WorkerBeeDied>,
+) {
+    for died in died_events.read() {
+        spawn_corpse(
+            &mut commands,
+            &textures,
+            AnimationSheet::Worker,
+            Transform::from_translation(died.position.extend(1.)),
+        );
+    }
+}
+
+fn kill_queen(
+    mut commands: Commands,
+    mut queen_query: Query<(&mut Visibility, &Children), With<QueenBee>>,
+    mut animations: Query<(Entity, &mut SpriteAnimation)>,
+) {
+    for (mut visibility, children) in &mut queen_query {
+        *visibility = Visibility::Inherited;
+        let mut iter = animations.iter_many_mut(children);
+        while let Some((entity, mut animation)) = iter.fetch_next() {
+            animation.play(AnimationKind::Death);
+            commands.entity(entity).insert(Dying);
+        }
+    }
+}
+
+/// Sprites on child entities follow the velocity of their parent body
+fn select_animation(
+    mut animations: Query<
+        (
+            &mut SpriteAnimation,
+            Option<&LinearVelocity>,
+            Option<&Parent>,
+        ),
+        Without<Dying>,
+    >,
+    bodies: Query<&LinearVelocity>,
+) {
+    for (mut animation, linear_velocity, parent) in &mut animations {
+        let Some(linear_velocity) =
+            linear_velocity.or_else(|| parent.and_then(|parent| bodies.get(parent.get()).ok()))
+        else {
+            continue;
+        };
+        let speed = linear_velocity.length() as f32;
+        let kind = if speed > DASH_SPEED {
+            AnimationKind::Dash
+        } else if speed > FLAP_SPEED {
+            AnimationKind::Flap
+        } else {
+            AnimationKind::Idle
+        };
+        animation.play(kind);
+    }
+}
+
+fn animate_sprites(
+    time: Res<Time>,
+    animation_assets: Res<AnimationAssets>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut animations: Query<(&mut SpriteAnimation, &mut TextureAtlasSprite)>,
+) {
+    let Some(library) = libraries.get(&animation_assets.library) else {
+        return;
+    };
+    for (mut animation, mut sprite) in &mut animations {
+        let Some(clip) = library.clip(animation.sheet, animation.kind) else {
+            continue;
+        };
+        let frame_time = 1.0 / clip.fps.max(f32::EPSILON);
+        if animation.timer.duration().as_secs_f32() != frame_time {
+            animation
+                .timer
+                .set_duration(std::time::Duration::from_secs_f32(frame_time));
+            animation.timer.set_mode(TimerMode::Repeating);
+        }
+
+        let frames = clip.last.saturating_sub(clip.first) + 1;
+        let advanced = animation
+            .timer
+            .tick(time.delta())
+            .times_finished_this_tick() as usize;
+        if !animation.finished && advanced > 0 {
+            let frame = animation.frame + advanced;
+            if clip.looping {
+                animation.frame = frame % frames;
+            } else if frame >= frames {
+                animation.frame = frames - 1;
+                animation.finished = true;
+            } else {
+                animation.frame = frame;
+            }
+        }
+        sprite.index = clip.first + animation.frame.min(frames - 1);
+    }
+}
+
+fn despawn_finished(
+    mut commands: Commands,
+    animations: Query<(Entity, &SpriteAnimation), With<DespawnWhenFinished>>,
+) {
+    for (entity, animation) in &animations {
+        if animation.finished {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::loading::ArenaAssets;
+use crate::replay::ReplayPlayback;
+use crate::ron_asset::RonAssetPlugin;
+use crate::GameState;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub struct ArenaPlugin;
+
+/// This plugin loads the [`ArenaLayout`] from `assets/arena.arena.ron` and applies it whenever the
+/// file changes outside of a round, the same way as the [`Tuning`](crate::tuning::Tuning). The
+/// arena editor writes the same file, changes made while it is open are ignored. A layout given
+/// with `--arena` replaces the file for the whole session.
+impl Plugin for ArenaPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_plugins(RonAssetPlugin::<ArenaLayout>::new(&["arena.ron"]))
+            .add_systems(
+                OnExit(GameState::Loading),
+                apply_layout.run_if(not(resource_exists::<ArenaOverride>())),
+            )
+            .add_systems(
+                Update,
+                reload_layout.run_if(
+                    resource_exists::<ArenaAssets>()
+                        .and_then(not(resource_exists::<ArenaOverride>())),
+                ),
+            );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const ARENA_SAVE_PATH: &str = "assets/arena.arena.ron";
+
+/// An axis aligned rectangle in world units
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+pub struct ArenaRect {
+    pub center: Vec2,
+    pub size: Vec2,
+}
+
+impl ArenaRect {
+    pub fn new(
+        center: Vec2,
+        size: Vec2,
+    ) -> Self {
+        ArenaRect { center, size }
+    }
+
+    pub fn contains(
+        &self,
+        point: Vec2,
+    ) -> bool {
+        let offset = (point - self.center).abs();
+        offset.x <= self.size.x / 2.0 && offset.y <= self.size.y / 2.0
+    }
+
+    pub fn area(&self) -> f32 {
+        self.size.x * self.size.y
+    }
+
+    pub fn random_point(
+        &self,
+        rng: &mut impl Rng,
+    ) -> Vec2 {
+        let half_size = self.size / 2.0;
+        self.center
+            + Vec2::new(
+                rng.gen_range(-half_size.x..=half_size.x),
+                rng.gen_range(-half_size.y..=half_size.y),
+            )
+    }
+}
+
+/// Where everything is placed in the arena, the walls around it are sized by the
+/// [`Tuning`](crate::tuning::Tuning)
+#[derive(Asset, Resource, Clone, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct ArenaLayout {
+    pub queen_spawn: Vec2,
+    /// Where the first flower of a round grows
+    pub first_flower: Vec2,
+    /// Walls inside the arena
+    pub walls: Vec<ArenaRect>,
+    /// Spikes that cost the queen a life
+    pub hazards: Vec<ArenaRect>,
+    /// Flowers grow in these zones, or anywhere in the arena if there are none
+    pub flower_zones: Vec<ArenaRect>,
+}
+
+impl Default for ArenaLayout {
+    fn default() -> Self {
+        ArenaLayout {
+            queen_spawn: Vec2::new(-350.0, 0.0),
+            first_flower: Vec2::new(350.0, 5.0),
+            walls: Vec::new(),
+            hazards: vec![
+                ArenaRect::new(Vec2::new(0.0, 150.0), Vec2::splat(40.0)),
+                ArenaRect::new(Vec2::new(0.0, -150.0), Vec2::splat(40.0)),
+            ],
+            flower_zones: Vec::new(),
+        }
+    }
+}
+
+impl ArenaLayout {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(path: &std::path::Path) -> Result<ArenaLayout, String> {
+        std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| ron::from_str(&contents).map_err(|error| error.to_string()))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_file(_path: &std::path::Path) -> Result<ArenaLayout, String> {
+        Err("Arenas can't be loaded from files on the web".to_string())
+    }
+
+    /// Writes the layout over the arena file in the assets, returns an error message on failure
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        std::fs::write(ARENA_SAVE_PATH, contents).map_err(|error| error.to_string())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) -> Result<(), String> {
+        Err("Arenas can't be saved on the web".to_string())
+    }
+}
+
+/// The layout was loaded from another file at launch, the arena file in the assets is ignored
+#[derive(Resource)]
+pub struct ArenaOverride;
+
+/// Marks a round that is test played from the arena editor, it doesn't count for the high scores
+#[derive(Resource)]
+pub struct TestPlay;
+
+fn apply_layout(
+    arena_assets: Res<ArenaAssets>,
+    assets: Res<Assets<ArenaLayout>>,
+    mut layout: ResMut<ArenaLayout>,
+    mut playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let Some(loaded) = assets.get(&arena_assets.layout) else {
+        return;
+    };
+    match playback.as_deref_mut() {
+        // A replay keeps the arena it was recorded in until it is over
+        Some(playback) => playback.previous_layout = loaded.clone(),
+        None => *layout = loaded.clone(),
+    }
+    info!("Applied the arena layout");
+}
+
+fn reload_layout(
+    mut asset_events: EventReader<AssetEvent<ArenaLayout>>,
+    mut reload_pending: Local<bool>,
+    state: Res<State<GameState>>,
+    arena_assets: Res<ArenaAssets>,
+    assets: Res<Assets<ArenaLayout>>,
+    layout: ResMut<ArenaLayout>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if asset_events
+        .read()
+        .any(|event| event.is_modified(&arena_assets.layout))
+    {
+        *reload_pending = true;
+    }
+    match state.get() {
+        // Flowers keep growing in the zones of the layout that the round was recorded with
+        GameState::Playing => {}
+        // The editor owns the file, reloading its own saves would undo the edits made since
+        GameState::Editor => *reload_pending = false,
+        _ if *reload_pending => {
+            *reload_pending = false;
+            apply_layout(arena_assets, assets, layout, playback);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,572 @@
+use crate::actions::{Actions, InputDevice};
+use crate::arena::{ArenaLayout, ArenaRect, TestPlay};
+use crate::bees::{spawn_arena, spawn_queen};
+use crate::console::console_open;
+use crate::difficulty::Difficulty;
+use crate::flowers::{flower_area, spawn_flower, FlowerKind};
+use crate::loading::TextureAssets;
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_xpbd_2d::prelude::*;
+
+pub struct ArenaEditorPlugin;
+
+/// This plugin adds the arena editor, opened from the menu and only compiled with the `dev`
+/// feature. Walls, hazards and flower zones are placed with the mouse and resized with the handle
+/// on the corner of the selected one, the queen spawn and the first flower are dragged around. The
+/// preview is spawned with the same code as a round, so the editor shows what will be played.
+///
+/// The layout is edited in place, a test play starts a round with it right away and returns to the
+/// editor when the round is over. Saving writes it to the arena file that the game loads.
+impl Plugin for ArenaEditorPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<ArenaEditor>()
+            .add_systems(OnEnter(GameState::Editor), (end_test_play, setup_editor))
+            .add_systems(
+                Update,
+                (
+                    (editor_shortcuts, edit_layout).run_if(not(console_open)),
+                    spawn_preview,
+                    update_status_text,
+                    draw_editor,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Editor)),
+            )
+            .add_systems(OnExit(GameState::Editor), cleanup_editor)
+            .add_systems(
+                Update,
+                stop_test_play
+                    .run_if(in_state(GameState::Playing).and_then(resource_exists::<TestPlay>())),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                return_to_editor.run_if(resource_exists::<TestPlay>()),
+            );
+    }
+}
+
+/// Positions and sizes snap to this grid
+const GRID: f32 = 10.0;
+const MIN_SIZE: f32 = 20.0;
+const HANDLE_SIZE: f32 = 12.0;
+/// Spawn points can be grabbed this far from their center
+const POINT_RADIUS: f32 = 20.0;
+const SELECTION_COLOR: Color = Color::YELLOW;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+enum Tool {
+    #[default]
+    Wall,
+    Hazard,
+    FlowerZone,
+}
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Wall => "Wall",
+            Tool::Hazard => "Hazard",
+            Tool::FlowerZone => "Flower zone",
+        }
+    }
+
+    fn default_size(self) -> Vec2 {
+        match self {
+            Tool::Wall => Vec2::new(120.0, 30.0),
+            Tool::Hazard => Vec2::splat(40.0),
+            Tool::FlowerZone => Vec2::new(200.0, 150.0),
+        }
+    }
+}
+
+/// Something placed in the [`ArenaLayout`], rectangles are referred to by their index
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Element {
+    QueenSpawn,
+    FirstFlower,
+    Wall(usize),
+    Hazard(usize),
+    FlowerZone(usize),
+}
+
+impl Element {
+    fn name(self) -> String {
+        match self {
+            Element::QueenSpawn => "Queen spawn".to_string(),
+            Element::FirstFlower => "First flower".to_string(),
+            Element::Wall(index) => format!("Wall {}", index + 1),
+            Element::Hazard(index) => format!("Hazard {}", index + 1),
+            Element::FlowerZone(index) => format!("Flower zone {}", index + 1),
+        }
+    }
+
+    fn rect(
+        self,
+        layout: &ArenaLayout,
+    ) -> Option<ArenaRect> {
+        match self {
+            Element::QueenSpawn | Element::FirstFlower => None,
+            Element::Wall(index) => layout.walls.get(index).copied(),
+            Element::Hazard(index) => layout.hazards.get(index).copied(),
+            Element::FlowerZone(index) => layout.flower_zones.get(index).copied(),
+        }
+    }
+
+    fn rect_mut(
+        self,
+        layout: &mut ArenaLayout,
+    ) -> Option<&mut ArenaRect> {
+        match self {
+            Element::QueenSpawn | Element::FirstFlower => None,
+            Element::Wall(index) => layout.walls.get_mut(index),
+            Element::Hazard(index) => layout.hazards.get_mut(index),
+            Element::FlowerZone(index) => layout.flower_zones.get_mut(index),
+        }
+    }
+
+    /// Removes a rectangle from the layout, the spawn points are always part of it
+    fn remove(
+        self,
+        layout: &mut ArenaLayout,
+    ) -> bool {
+        let (rects, index) = match self {
+            Element::QueenSpawn | Element::FirstFlower => return false,
+            Element::Wall(index) => (&mut layout.walls, index),
+            Element::Hazard(index) => (&mut layout.hazards, index),
+            Element::FlowerZone(index) => (&mut layout.flower_zones, index),
+        };
+        if index >= rects.len() {
+            return false;
+        }
+        rects.remove(index);
+        true
+    }
+
+    fn position(
+        self,
+        layout: &ArenaLayout,
+    ) -> Option<Vec2> {
+        match self {
+            Element::QueenSpawn => Some(layout.queen_spawn),
+            Element::FirstFlower => Some(layout.first_flower),
+            _ => self.rect(layout).map(|rect| rect.center),
+        }
+    }
+
+    fn set_position(
+        self,
+        layout: &mut ArenaLayout,
+        position: Vec2,
+    ) {
+        match self {
+            Element::QueenSpawn => layout.queen_spawn = position,
+            Element::FirstFlower => layout.first_flower = position,
+            _ => {
+                if let Some(rect) = self.rect_mut(layout) {
+                    rect.center = position;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum DragKind {
+    Move {
+        /// From the grabbed point to the position of the element
+        offset: Vec2,
+    },
+    Resize {
+        /// The corner opposite of the handle stays where it is
+        anchor: Vec2,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    element: Element,
+    kind: DragKind,
+}
+
+#[derive(Resource, Default)]
+struct ArenaEditor {
+    tool: Tool,
+    selected: Option<Element>,
+    drag: Option<Drag>,
+    /// Result of the last save
+    message: String,
+}
+
+/// Entities of the arena preview, despawned whenever the layout changes
+#[derive(Component)]
+struct EditorPreview;
+
+#[derive(Component)]
+struct EditorUi;
+
+#[derive(Component)]
+struct EditorStatusText;
+
+fn end_test_play(
+    mut commands: Commands,
+    round_entities: Query<Entity, With<RoundEntity>>,
+) {
+    // Leaving a test play early skips the round cleanup of the results screen
+    for entity in &round_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<TestPlay>();
+}
+
+fn setup_editor(
+    mut commands: Commands,
+    mut editor: ResMut<ArenaEditor>,
+) {
+    editor.drag = None;
+    editor.message.clear();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(5.0),
+                    left: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            EditorUi,
+        ))
+        .with_children(|children| {
+            children.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                EditorStatusText,
+            ));
+            children.spawn(TextBundle::from_section(
+                "1: Wall, 2: Hazard, 3: Flower zone, click to place\n\
+                 Drag to move, drag the corner handle to resize, Delete removes\n\
+                 Ctrl+S: Save, Return: Test play, Escape: Menu",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn editor_shortcuts(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editor: ResMut<ArenaEditor>,
+    mut layout: ResMut<ArenaLayout>,
+    mut actions: ResMut<Actions>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (key, tool) in [
+        (KeyCode::Key1, Tool::Wall),
+        (KeyCode::Key2, Tool::Hazard),
+        (KeyCode::Key3, Tool::FlowerZone),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            editor.tool = tool;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        if let Some(element) = editor.selected {
+            if element.remove(&mut layout) {
+                editor.selected = None;
+                editor.drag = None;
+            }
+        }
+    }
+
+    let control = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if control && keyboard_input.just_pressed(KeyCode::S) {
+        editor.message = match layout.save() {
+            Ok(()) => "Saved".to_string(),
+            Err(error) => format!("Failed to save {error}"),
+        };
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        commands.insert_resource(TestPlay);
+        actions.input_device = InputDevice::Keyboard;
+        next_state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn edit_layout(
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut editor: ResMut<ArenaEditor>,
+    mut layout: ResMut<ArenaLayout>,
+) {
+    if mouse_input.just_released(MouseButton::Left) {
+        editor.drag = None;
+    }
+    let Some(cursor) = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Some(cursor) = cameras.iter().find_map(|(camera, camera_transform)| {
+        camera.viewport_to_world_2d(camera_transform, cursor)
+    }) else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let drag = grab(editor.selected, &layout, cursor).unwrap_or_else(|| Drag {
+            element: place(editor.tool, &mut layout, snap(cursor)),
+            kind: DragKind::Move { offset: Vec2::ZERO },
+        });
+        editor.selected = Some(drag.element);
+        editor.drag = Some(drag);
+    }
+
+    // Only touch the layout when something moved, every change respawns the preview
+    let Some(drag) = editor.drag else {
+        return;
+    };
+    match drag.kind {
+        DragKind::Move { offset } => {
+            let position = snap(cursor + offset);
+            if drag.element.position(&layout) != Some(position) {
+                drag.element.set_position(&mut layout, position);
+            }
+        }
+        DragKind::Resize { anchor } => {
+            let corner = snap(cursor).max(anchor + MIN_SIZE);
+            let resized = ArenaRect::new((anchor + corner) / 2.0, corner - anchor);
+            if drag.element.rect(&layout) != Some(resized) {
+                if let Some(rect) = drag.element.rect_mut(&mut layout) {
+                    *rect = resized;
+                }
+            }
+        }
+    }
+}
+
+fn snap(position: Vec2) -> Vec2 {
+    (position / GRID).round() * GRID
+}
+
+/// The element under the cursor, the handle of the selected rectangle comes first and the spawn
+/// points and hazards are picked over the larger walls and zones
+fn grab(
+    selected: Option<Element>,
+    layout: &ArenaLayout,
+    cursor: Vec2,
+) -> Option<Drag> {
+    if let Some(element) = selected {
+        if let Some(rect) = element.rect(layout) {
+            let handle = rect.center + rect.size / 2.0;
+            if (cursor - handle).abs().max_element() <= HANDLE_SIZE / 2.0 {
+                return Some(Drag {
+                    element,
+                    kind: DragKind::Resize {
+                        anchor: rect.center - rect.size / 2.0,
+                    },
+                });
+            }
+        }
+    }
+
+    let points = [
+        (Element::QueenSpawn, layout.queen_spawn),
+        (Element::FirstFlower, layout.first_flower),
+    ]
+    .into_iter()
+    .filter(|(_, point)| point.distance(cursor) <= POINT_RADIUS);
+    let hazards = rect_elements(&layout.hazards, Element::Hazard);
+    let walls = rect_elements(&layout.walls, Element::Wall);
+    let zones = rect_elements(&layout.flower_zones, Element::FlowerZone);
+    let (element, position) = points
+        .chain(
+            hazards
+                .chain(walls)
+                .chain(zones)
+                .filter(|(_, rect)| rect.contains(cursor))
+                .map(|(element, rect)| (element, rect.center)),
+        )
+        .next()?;
+    Some(Drag {
+        element,
+        kind: DragKind::Move {
+            offset: position - cursor,
+        },
+    })
+}
+
+/// The rectangles with their elements, the last placed one first because it is drawn on top
+fn rect_elements(
+    rects: &[ArenaRect],
+    element: fn(usize) -> Element,
+) -> impl Iterator<Item = (Element, ArenaRect)> + '_ {
+    rects
+        .iter()
+        .enumerate()
+        .rev()
+        .map(move |(index, rect)| (element(index), *rect))
+}
+
+fn place(
+    tool: Tool,
+    layout: &mut ArenaLayout,
+    position: Vec2,
+) -> Element {
+    let rect = ArenaRect::new(position, tool.default_size());
+    match tool {
+        Tool::Wall => {
+            layout.walls.push(rect);
+            Element::Wall(layout.walls.len() - 1)
+        }
+        Tool::Hazard => {
+            layout.hazards.push(rect);
+            Element::Hazard(layout.hazards.len() - 1)
+        }
+        Tool::FlowerZone => {
+            layout.flower_zones.push(rect);
+            Element::FlowerZone(layout.flower_zones.len() - 1)
+        }
+    }
+}
+
+/// Spawns the arena, the queen and the first flower just like a round does
+fn spawn_preview(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+    previews: Query<Entity, With<EditorPreview>>,
+) {
+    if !previews.is_empty() && !layout.is_changed() && !tuning.is_changed() {
+        return;
+    }
+    for entity in &previews {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut entities = spawn_arena(&mut commands, &textures, &tuning, &layout);
+    let queen = spawn_queen(
+        &mut commands,
+        &textures,
+        &difficulty,
+        &tuning,
+        layout.queen_spawn,
+    );
+    // The queen stays on her spawn point instead of being pushed out of overlapping walls
+    commands.entity(queen).insert(RigidBody::Static);
+    entities.push(queen);
+    entities.push(spawn_flower(
+        &mut commands,
+        &textures,
+        &tuning,
+        FlowerKind::Daisy,
+        layout.first_flower,
+    ));
+    for entity in entities {
+        commands.entity(entity).insert(EditorPreview);
+    }
+}
+
+fn update_status_text(
+    editor: Res<ArenaEditor>,
+    mut text_query: Query<&mut Text, With<EditorStatusText>>,
+) {
+    if !editor.is_changed() {
+        return;
+    }
+    let selected = editor.selected.map_or("Nothing".to_string(), Element::name);
+    for mut text in &mut text_query {
+        text.sections[0].value = format!(
+            "Tool: {}  Selected: {}  {}",
+            editor.tool.name(),
+            selected,
+            editor.message
+        );
+    }
+}
+
+fn draw_editor(
+    mut gizmos: Gizmos,
+    editor: Res<ArenaEditor>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+) {
+    if layout.flower_zones.is_empty() {
+        let color = Color::PINK.with_a(0.3);
+        gizmos.rect_2d(Vec2::ZERO, 0.0, flower_area(&tuning) * 2.0, color);
+    }
+    for zone in &layout.flower_zones {
+        gizmos.rect_2d(zone.center, 0.0, zone.size, Color::PINK);
+    }
+    for (element, point) in [
+        (Element::QueenSpawn, layout.queen_spawn),
+        (Element::FirstFlower, layout.first_flower),
+    ] {
+        let color = if editor.selected == Some(element) {
+            SELECTION_COLOR
+        } else {
+            Color::WHITE
+        };
+        gizmos.circle_2d(point, POINT_RADIUS, color);
+    }
+
+    let Some(rect) = editor.selected.and_then(|element| element.rect(&layout)) else {
+        return;
+    };
+    gizmos.rect_2d(rect.center, 0.0, rect.size, SELECTION_COLOR);
+    gizmos.rect_2d(
+        rect.center + rect.size / 2.0,
+        0.0,
+        Vec2::splat(HANDLE_SIZE),
+        SELECTION_COLOR,
+    );
+}
+
+fn cleanup_editor(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<EditorPreview>, With<EditorUi>)>>,
+) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Escape ends a test play early
+fn stop_test_play(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Editor);
+    }
+}
+
+/// Test plays skip the results screen
+fn return_to_editor(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Editor);
+}
@@ -1,52 +1,467 @@
-use crate::actions::Actions;
-use crate::bees::flower_collision;
+use crate::bees::{Production, QueenBee};
+use crate::camera::CameraController;
+use crate::events::{FlowerCollected, QueenHit, WorkerBeeDied};
 use crate::loading::AudioAssets;
+use crate::scoreboard::Score;
+use crate::settings::Settings;
 use crate::GameState;
 use bevy::prelude::*;
+use bevy::utils::{Duration, HashMap};
 use bevy_kira_audio::prelude::*;
 
 pub struct InternalAudioPlugin;
 
+/// This plugin mixes all sounds of the game through four channels, see [`MixerChannel`]. Their
+/// volumes come from the [`Settings`], important sound effects duck the music for a moment and
+/// every channel has a cap on the number of sounds playing at once.
+///
+/// The music is adaptive. Every state has its own track and switching states crossfades between
+/// them. During a round the music speeds up and a buzzing swarm layer fades in as the hive grows.
+/// Gameplay systems play sounds by sending [`PlaySound`] events. Sounds with a position are
+/// panned and attenuated by where they happen relative to the camera.
 impl Plugin for InternalAudioPlugin {
     fn build(
         &self,
         app: &mut App,
     ) {
         app.add_plugins(AudioPlugin)
-            .add_audio_channel::<Background>()
+            .add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<SfxChannel>()
+            .add_audio_channel::<UiChannel>()
+            .add_audio_channel::<AmbientChannel>()
+            .add_event::<PlaySound>()
+            .init_resource::<Music>()
+            .init_resource::<Mixer>()
+            .add_systems(OnEnter(GameState::Menu), play_menu_music)
+            .add_systems(OnEnter(GameState::Editor), play_menu_music)
             .add_systems(OnEnter(GameState::Playing), play_background_music)
+            .add_systems(OnEnter(GameState::GameOver), play_results_music)
             .add_systems(
                 Update,
-                play_flower_sound
-                    .after(flower_collision)
-                    .run_if(in_state(GameState::Playing)),
+                (play_gameplay_sounds, update_music_intensity).run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                (play_sounds, update_music_volume)
+                    .chain()
+                    .run_if(resource_exists::<AudioAssets>()),
             );
     }
 }
 
+const CROSSFADE_TIME: Duration = Duration::from_millis(1500);
+const MUSIC_VOLUME: f64 = 0.3;
+const SWARM_LAYER_VOLUME: f64 = 0.35;
+/// The gameplay track plays this much faster at full intensity
+const MAX_PLAYBACK_RATE_INCREASE: f64 = 0.15;
+/// The swarm is at full intensity with this many worker bees
+const FULL_INTENSITY_BEES: f64 = 150.0;
+/// ... or with this much production
+const FULL_INTENSITY_PRODUCTION: f64 = 3.0;
+/// Intensity changes smaller than this don't restart the playback rate tween
+const INTENSITY_STEP: f64 = 0.05;
+/// How fast the swarm layer follows the intensity, per second
+const INTENSITY_SPEED: f64 = 2.0;
+/// Music volume while ducked
+const DUCK_VOLUME: f64 = 0.35;
+const DUCK_TIME: f32 = 0.6;
+/// How fast the music ducks and recovers, in volume per second
+const DUCK_SPEED: f64 = 4.0;
+/// Panning of a sound at the edge of the view, 0.5 would be hard left or right
+const MAX_PAN: f64 = 0.4;
+/// Volume lost per half view width of distance from the center of the view
+const ATTENUATION: f64 = 0.35;
+const MIN_ATTENUATION: f64 = 0.2;
+
+/// The channels of the mixer
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MixerChannel {
+    Music,
+    Sfx,
+    Ui,
+    Ambient,
+}
+
+impl MixerChannel {
+    /// The maximum number of sounds that play on the channel at once, further sounds are dropped
+    fn max_instances(&self) -> usize {
+        match self {
+            MixerChannel::Music => 4,
+            MixerChannel::Sfx => 12,
+            MixerChannel::Ui => 4,
+            MixerChannel::Ambient => 4,
+        }
+    }
+}
+
 #[derive(Resource)]
-struct Background;
+struct MusicChannel;
+
+#[derive(Resource)]
+struct SfxChannel;
+
+#[derive(Resource)]
+struct UiChannel;
+
+#[derive(Resource)]
+struct AmbientChannel;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundEffect {
+    FlowerGet,
+    FlowerStinger,
+    BeeDeath,
+    QueenHit,
+    UiClick,
+}
+
+impl SoundEffect {
+    fn source(
+        &self,
+        audio_assets: &AudioAssets,
+    ) -> Handle<AudioSource> {
+        match self {
+            SoundEffect::FlowerGet => audio_assets.flower.clone(),
+            SoundEffect::FlowerStinger => audio_assets.flower_stinger.clone(),
+            SoundEffect::BeeDeath | SoundEffect::QueenHit => audio_assets.bee_death.clone(),
+            SoundEffect::UiClick => audio_assets.ui_click.clone(),
+        }
+    }
+
+    fn channel(&self) -> MixerChannel {
+        match self {
+            SoundEffect::FlowerGet
+            | SoundEffect::FlowerStinger
+            | SoundEffect::BeeDeath
+            | SoundEffect::QueenHit => MixerChannel::Sfx,
+            SoundEffect::UiClick => MixerChannel::Ui,
+        }
+    }
+
+    fn volume(&self) -> f64 {
+        match self {
+            SoundEffect::FlowerGet => 0.5,
+            SoundEffect::FlowerStinger => 0.4,
+            SoundEffect::BeeDeath => 0.25,
+            SoundEffect::QueenHit => 0.7,
+            SoundEffect::UiClick => 0.4,
+        }
+    }
+
+    fn playback_rate(&self) -> f64 {
+        match self {
+            SoundEffect::QueenHit => 0.6,
+            _ => 1.0,
+        }
+    }
+
+    /// Important sounds duck the music so they can be heard over it
+    fn ducks_music(&self) -> bool {
+        matches!(self, SoundEffect::FlowerGet | SoundEffect::QueenHit)
+    }
+}
+
+#[derive(Event)]
+pub struct PlaySound {
+    pub sound: SoundEffect,
+    /// World position of the sound, `None` plays it centered at full volume
+    pub position: Option<Vec2>,
+}
 
 #[derive(Resource)]
-struct FlowerAudio(Handle<AudioInstance>);
+struct Mixer {
+    /// Sounds started through [`PlaySound`], per channel
+    playing: HashMap<MixerChannel, Vec<Handle<AudioInstance>>>,
+    duck: f64,
+    duck_timer: Timer,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        let mut duck_timer = Timer::from_seconds(DUCK_TIME, TimerMode::Once);
+        duck_timer.tick(duck_timer.duration());
+        Mixer {
+            playing: HashMap::default(),
+            duck: 1.0,
+            duck_timer,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MusicTrack {
+    Menu,
+    Gameplay,
+    Results,
+}
+
+#[derive(Resource, Default)]
+struct Music {
+    track: Option<MusicTrack>,
+    /// The looped track that is currently playing, or fading in
+    main: Option<Handle<AudioInstance>>,
+    swarm_layer: Option<Handle<AudioInstance>>,
+    /// Goes from 0 to 1 while the current track fades in
+    fade: f64,
+    intensity: f64,
+    /// The swarm layer volume, following `intensity`
+    swarm_level: f64,
+}
+
+impl Music {
+    /// Fades out the current track, returns `false` if `track` is already playing
+    fn switch_to(
+        &mut self,
+        track: MusicTrack,
+        audio_instances: &mut Assets<AudioInstance>,
+    ) -> bool {
+        if self.track == Some(track) {
+            return false;
+        }
+        for handle in [self.main.take(), self.swarm_layer.take()]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(instance) = audio_instances.get_mut(&handle) {
+                instance.stop(AudioTween::linear(CROSSFADE_TIME));
+            }
+        }
+        self.track = Some(track);
+        self.fade = 0.0;
+        self.intensity = 0.0;
+        self.swarm_level = 0.0;
+        true
+    }
+}
+
+fn play_menu_music(
+    mut music: ResMut<Music>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    if !music.switch_to(MusicTrack::Menu, &mut audio_instances) {
+        return;
+    }
+    // Volumes start at zero and are faded in by `update_music_volume`
+    music.main = Some(
+        music_channel
+            .play(audio_assets.menu_music.clone())
+            .looped()
+            .with_volume(0.0)
+            .handle(),
+    );
+}
 
 fn play_background_music(
-    background_channel: Res<AudioChannel<Background>>,
+    mut music: ResMut<Music>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    ambient_channel: Res<AudioChannel<AmbientChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    background_channel
-        .play(audio_assets.background_music.clone())
-        .looped()
-        .with_volume(0.3);
+    if !music.switch_to(MusicTrack::Gameplay, &mut audio_instances) {
+        return;
+    }
+    music.main = Some(
+        music_channel
+            .play(audio_assets.background_music.clone())
+            .looped()
+            .with_volume(0.0)
+            .handle(),
+    );
+    music.swarm_layer = Some(
+        ambient_channel
+            .play(audio_assets.swarm_layer.clone())
+            .looped()
+            .with_volume(0.0)
+            .handle(),
+    );
 }
 
-fn play_flower_sound(
-    mut actions: ResMut<Actions>,
-    audio: Res<Audio>,
+fn play_results_music(
+    mut music: ResMut<Music>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
     audio_assets: Res<AudioAssets>,
 ) {
-    if actions.flower_gotten {
-        actions.flower_gotten = false;
-        audio.play(audio_assets.flower.clone()).with_volume(0.5);
+    if !music.switch_to(MusicTrack::Results, &mut audio_instances) {
+        return;
     }
+    music.main = Some(
+        music_channel
+            .play(audio_assets.results_music.clone())
+            .looped()
+            .with_volume(0.0)
+            .handle(),
+    );
+}
+
+fn update_music_intensity(
+    score: Res<Score>,
+    queen_query: Query<&Production, With<QueenBee>>,
+    mut music: ResMut<Music>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    let production = queen_query
+        .get_single()
+        .map_or(0.0, |production| production.0 as f64);
+    let intensity = (score.bees as f64 / FULL_INTENSITY_BEES)
+        .max((production - 1.0) / (FULL_INTENSITY_PRODUCTION - 1.0))
+        .clamp(0.0, 1.0);
+    if (intensity - music.intensity).abs() < INTENSITY_STEP {
+        return;
+    }
+    music.intensity = intensity;
+
+    if let Some(instance) = music
+        .main
+        .as_ref()
+        .and_then(|handle| audio_instances.get_mut(handle))
+    {
+        instance.set_playback_rate(
+            1.0 + MAX_PLAYBACK_RATE_INCREASE * intensity,
+            AudioTween::linear(Duration::from_millis(500)),
+        );
+    }
+}
+
+/// Applies fades, ducking, the swarm layer intensity and the channel volumes to the music
+fn update_music_volume(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut mixer: ResMut<Mixer>,
+    mut music: ResMut<Music>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    let delta_time = time.delta_seconds_f64();
+    let duck_target = if mixer.duck_timer.tick(time.delta()).finished() {
+        1.0
+    } else {
+        DUCK_VOLUME
+    };
+    let duck_step = DUCK_SPEED * delta_time;
+    mixer.duck += (duck_target - mixer.duck).clamp(-duck_step, duck_step);
+
+    music.fade = (music.fade + delta_time / CROSSFADE_TIME.as_secs_f64()).min(1.0);
+    let swarm_step = INTENSITY_SPEED * delta_time;
+    music.swarm_level += (music.intensity - music.swarm_level).clamp(-swarm_step, swarm_step);
+
+    let main_volume =
+        MUSIC_VOLUME * music.fade * mixer.duck * settings.channel_volume(MixerChannel::Music);
+    let swarm_volume = SWARM_LAYER_VOLUME
+        * music.fade
+        * music.swarm_level
+        * settings.channel_volume(MixerChannel::Ambient);
+    for (handle, volume) in [
+        (&music.main, main_volume),
+        (&music.swarm_layer, swarm_volume),
+    ] {
+        if let Some(instance) = handle
+            .as_ref()
+            .and_then(|handle| audio_instances.get_mut(handle))
+        {
+            instance.set_volume(volume, AudioTween::default());
+        }
+    }
+}
+
+fn play_sounds(
+    mut sound_events: EventReader<PlaySound>,
+    mut mixer: ResMut<Mixer>,
+    settings: Res<Settings>,
+    cameras: Query<(&GlobalTransform, &OrthographicProjection), With<CameraController>>,
+    audio_assets: Res<AudioAssets>,
+    music_channel: Res<AudioChannel<MusicChannel>>,
+    sfx_channel: Res<AudioChannel<SfxChannel>>,
+    ui_channel: Res<AudioChannel<UiChannel>>,
+    ambient_channel: Res<AudioChannel<AmbientChannel>>,
+) {
+    let channel_state = |channel: MixerChannel, handle: &Handle<AudioInstance>| match channel {
+        MixerChannel::Music => music_channel.state(handle),
+        MixerChannel::Sfx => sfx_channel.state(handle),
+        MixerChannel::Ui => ui_channel.state(handle),
+        MixerChannel::Ambient => ambient_channel.state(handle),
+    };
+    for (channel, playing) in mixer.playing.iter_mut() {
+        playing.retain(|handle| channel_state(*channel, handle) != PlaybackState::Stopped);
+    }
+
+    let listener = cameras.get_single().ok().map(|(transform, projection)| {
+        (transform.translation().truncate(), projection.area.size())
+    });
+
+    for PlaySound { sound, position } in sound_events.read() {
+        let channel = sound.channel();
+        let playing = mixer.playing.entry(channel).or_default();
+        if playing.len() >= channel.max_instances() {
+            continue;
+        }
+        let (panning, attenuation) = match (position, listener) {
+            (Some(position), Some((center, view_size))) => {
+                spatialize(*position - center, view_size)
+            }
+            _ => (0.5, 1.0),
+        };
+
+        let source = sound.source(&audio_assets);
+        let volume = sound.volume() * attenuation * settings.channel_volume(channel);
+        let mut command = match channel {
+            MixerChannel::Music => music_channel.play(source),
+            MixerChannel::Sfx => sfx_channel.play(source),
+            MixerChannel::Ui => ui_channel.play(source),
+            MixerChannel::Ambient => ambient_channel.play(source),
+        };
+        let handle = command
+            .with_volume(volume)
+            .with_panning(panning)
+            .with_playback_rate(sound.playback_rate())
+            .handle();
+        playing.push(handle);
+        if sound.ducks_music() {
+            mixer.duck_timer.reset();
+        }
+    }
+}
+
+fn play_gameplay_sounds(
+    mut collected_events: EventReader<FlowerCollected>,
+    mut died_events: EventReader<WorkerBeeDied>,
+    mut hit_events: EventReader<QueenHit>,
+    mut sound_events: EventWriter<PlaySound>,
+) {
+    for hit in hit_events.read() {
+        sound_events.send(PlaySound {
+            sound: SoundEffect::QueenHit,
+            position: Some(hit.position),
+        });
+    }
+    for collected in collected_events.read() {
+        sound_events.send(PlaySound {
+            sound: SoundEffect::FlowerGet,
+            position: Some(collected.position),
+        });
+        sound_events.send(PlaySound {
+            sound: SoundEffect::FlowerStinger,
+            position: None,
+        });
+    }
+    for died in died_events.read() {
+        sound_events.send(PlaySound {
+            sound: SoundEffect::BeeDeath,
+            position: Some(died.position),
+        });
+    }
+}
+
+/// Panning and volume factor of a sound at `offset` from the center of a view of `view_size`
+fn spatialize(
+    offset: Vec2,
+    view_size: Vec2,
+) -> (f64, f64) {
+    let half_size = (view_size / 2.0).max(Vec2::ONE);
+    let panning = 0.5 + MAX_PAN * (offset.x / half_size.x).clamp(-1.0, 1.0) as f64;
+    let distance = (offset / half_size).length() as f64;
+    let attenuation = (1.0 - ATTENUATION * distance).clamp(MIN_ATTENUATION, 1.0);
+    (panning, attenuation)
 }
@@ -1,9 +1,15 @@
 use bevy::prelude::*;
 
-use crate::bees::{Production, QueenBee, WorkerBee};
+use crate::animation::AnimationSheet;
+use crate::bee_visuals::bee_visual_bundle;
+use crate::bees::{Layer, Production, ProductionProgress, QueenBee, WorkerBee};
+use crate::difficulty::Difficulty;
+use crate::events::WorkerBeeSpawned;
+use crate::interpolation::Interpolated;
 use crate::loading::TextureAssets;
-use crate::scoreboard::Score;
-use crate::GameState;
+use crate::pickups::{effect_active, EffectKind, TimedEffect};
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
 use bevy_xpbd_2d::{math::*, prelude::*};
 
 pub struct BeeSpawnerPlugin;
@@ -14,34 +20,76 @@ impl Plugin for BeeSpawnerPlugin {
         app: &mut App,
     ) {
         app.add_systems(
-            Update,
-            spawn_worker_bee.run_if(in_state(GameState::Playing)),
+            FixedUpdate,
+            spawn_worker_bee
+                .before(PhysicsSet::Prepare)
+                .run_if(in_state(GameState::Playing)),
         );
     }
 }
 
 fn spawn_worker_bee(
     mut commands: Commands,
-    mut time_produced: Local<f32>,
     time: Res<Time>,
-    queen_query: Query<(&Transform, &Production), With<QueenBee>>,
+    mut queen_query: Query<(&Transform, &Production, &mut ProductionProgress), With<QueenBee>>,
+    effects: Query<&TimedEffect>,
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
     textures: Res<TextureAssets>,
-    mut score: ResMut<Score>,
+    mut spawned_events: EventWriter<WorkerBeeSpawned>,
 ) {
-    for (transform, production) in &queen_query {
-        if (time.elapsed_seconds() - *time_produced) > (1.0 / production.0) {
-            *time_produced = time.elapsed_seconds();
-            commands.spawn((
-                SpriteBundle {
-                    texture: textures.bee.clone(),
-                    transform: transform.clone(),
-                    ..default()
-                },
-                RigidBody::Dynamic,
-                Collider::ball(7.0 as Scalar),
-                WorkerBee,
-            ));
-            score.bees += 1;
+    let paused = effect_active(&effects, EffectKind::ProductionPause);
+    for (transform, production, mut progress) in &mut queen_query {
+        if paused {
+            progress.0 = 0.0;
+            continue;
+        }
+        progress.0 += time.delta_seconds();
+        let spawn_interval = difficulty.settings.spawn_interval_scaling / production.0;
+        if progress.0 > spawn_interval {
+            progress.0 = 0.0;
+            let worker = spawn_worker_bee_at(
+                &mut commands,
+                textures.bee.clone(),
+                tuning.worker_radius,
+                transform,
+            );
+            spawned_events.send(WorkerBeeSpawned {
+                entity: worker,
+                position: transform.translation.truncate(),
+            });
         }
     }
 }
+
+/// Spawns a worker bee at `transform`, usually the queen's
+pub(crate) fn spawn_worker_bee_at(
+    commands: &mut Commands,
+    texture: Handle<TextureAtlas>,
+    radius: f32,
+    transform: &Transform,
+) -> Entity {
+    commands
+        .spawn((
+            SpatialBundle::from_transform(transform.clone()),
+            RigidBody::Dynamic,
+            Collider::ball(radius as Scalar),
+            CollisionLayers::new(
+                [Layer::Worker],
+                [
+                    Layer::Queen,
+                    Layer::Worker,
+                    Layer::Wall,
+                    Layer::Flower,
+                    Layer::Wasp,
+                ],
+            ),
+            WorkerBee,
+            Interpolated::new(transform.translation.truncate()),
+            RoundEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn(bee_visual_bundle(texture, AnimationSheet::Worker));
+        })
+        .id()
+}
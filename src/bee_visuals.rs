@@ -0,0 +1,135 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::animation::{AnimationSheet, SpriteAnimation};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+
+pub struct BeeVisualsPlugin;
+
+/// This plugin drives the sprites of the queen and the worker bees. The sprites live on child
+/// entities of the physics bodies, so they can tilt toward the direction of flight, stretch with
+/// speed, squash on impact and turn around smoothly without touching the simulation.
+impl Plugin for BeeVisualsPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_systems(
+            Update,
+            orient_bee_visuals
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::GameOver))),
+        )
+        .add_systems(
+            PostProcessCollisions,
+            squash_on_collision.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// The sprites face right, they never tilt further than this away from the horizon
+const MAX_TILT: f32 = 1.2;
+const TILT_SPEED: f32 = 10.0;
+/// How fast a bee turns around, in full turns per second
+const FLIP_SPEED: f32 = 6.0;
+/// Below this horizontal speed a bee keeps facing the way it was
+const FLIP_MIN_SPEED: f32 = 10.0;
+/// Stretch along the direction of flight per unit of speed
+const STRETCH_PER_SPEED: f32 = 0.0006;
+const MAX_STRETCH: f32 = 0.3;
+const SQUASH_AMOUNT: f32 = 0.35;
+const SQUASH_DECAY: f32 = 6.0;
+
+/// The sprite of a bee, spawned as a child of its physics body
+#[derive(Component)]
+pub struct BeeVisual {
+    /// Goes from -1 when facing left to 1 when facing right
+    facing: f32,
+    tilt: f32,
+    /// Set to 1 by a collision and decays back to 0
+    squash: f32,
+}
+
+impl Default for BeeVisual {
+    fn default() -> Self {
+        BeeVisual {
+            facing: 1.0,
+            tilt: 0.0,
+            squash: 0.0,
+        }
+    }
+}
+
+pub fn bee_visual_bundle(
+    texture_atlas: Handle<TextureAtlas>,
+    sheet: AnimationSheet,
+) -> impl Bundle {
+    (
+        SpriteSheetBundle {
+            texture_atlas,
+            ..default()
+        },
+        SpriteAnimation::new(sheet),
+        BeeVisual::default(),
+    )
+}
+
+fn orient_bee_visuals(
+    time: Res<Time>,
+    bodies: Query<&LinearVelocity>,
+    mut visuals: Query<(&mut BeeVisual, &mut Transform, &Parent)>,
+) {
+    let delta_time = time.delta_seconds();
+    let tilt_step = (TILT_SPEED * delta_time).min(1.0);
+    for (mut visual, mut transform, parent) in &mut visuals {
+        let Ok(linear_velocity) = bodies.get(parent.get()) else {
+            continue;
+        };
+        let velocity = Vec2::new(linear_velocity.x as f32, linear_velocity.y as f32);
+
+        if velocity.x.abs() > FLIP_MIN_SPEED {
+            let target = velocity.x.signum();
+            let step = 2.0 * FLIP_SPEED * delta_time;
+            visual.facing += (target - visual.facing).clamp(-step, step);
+        }
+        let direction = visual.facing.signum();
+
+        let target_tilt = if velocity.length() > FLIP_MIN_SPEED {
+            velocity
+                .y
+                .atan2(velocity.x.abs())
+                .clamp(-MAX_TILT, MAX_TILT)
+        } else {
+            0.0
+        };
+        visual.tilt += (target_tilt - visual.tilt) * tilt_step;
+        visual.squash = (visual.squash - SQUASH_DECAY * delta_time).max(0.0);
+
+        let stretch = (velocity.length() * STRETCH_PER_SPEED).min(MAX_STRETCH);
+        let squash = visual.squash * SQUASH_AMOUNT;
+        transform.rotation = Quat::from_rotation_z(visual.tilt * direction);
+        transform.scale = Vec3::new(
+            visual.facing * (1.0 + stretch + squash),
+            1.0 / (1.0 + stretch) - squash,
+            1.0,
+        );
+    }
+}
+
+fn squash_on_collision(
+    mut collision_event_reader: EventReader<CollisionStarted>,
+    bodies: Query<&Children>,
+    mut visuals: Query<&mut BeeVisual>,
+) {
+    for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
+        for entity in [entity1, entity2] {
+            let Ok(children) = bodies.get(*entity) else {
+                continue;
+            };
+            let mut iter = visuals.iter_many_mut(children);
+            while let Some(mut visual) = iter.fetch_next() {
+                visual.squash = 1.0;
+            }
+        }
+    }
+}
@@ -1,10 +1,17 @@
 #![allow(clippy::unnecessary_cast)]
 
-use crate::actions::{gamepad_system, Actions};
+use crate::actions::{Actions, InputSet};
+use crate::animation::AnimationSheet;
+use crate::arena::{ArenaLayout, ArenaRect};
 use crate::bee_spawner::BeeSpawnerPlugin;
+use crate::bee_visuals::bee_visual_bundle;
+use crate::difficulty::Difficulty;
+use crate::health::{DamageSource, Invulnerable, Lives, QueenDamaged};
+use crate::interpolation::Interpolated;
 use crate::loading::TextureAssets;
-use crate::scoreboard::Score;
-use crate::GameState;
+use crate::pickups::{effect_active, EffectKind, TimedEffect};
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity, FIXED_TIMESTEP_HZ};
 use bevy::prelude::*;
 use bevy_xpbd_2d::{math::*, prelude::*};
 
@@ -16,25 +23,22 @@ impl Plugin for BeesPlugin {
         app: &mut App,
     ) {
         app.add_plugins(BeeSpawnerPlugin)
-            .add_plugins(PhysicsPlugins::default())
-            .insert_resource(ClearColor(Color::rgb(0.161, 0.678, 1.0)))
-            .insert_resource(SubstepCount(6))
+            .add_plugins(PhysicsPlugins::new(FixedUpdate))
+            // The physics takes exactly one step of the fixed clock whenever it runs
+            .insert_resource(Time::new_with(Physics::fixed_once_hz(FIXED_TIMESTEP_HZ)))
+            .insert_resource(SubstepCount(Difficulty::default().settings.substep_count))
             .insert_resource(Gravity(Vector::ZERO))
             .add_systems(OnEnter(GameState::Playing), setup)
             .add_systems(
-                Update,
-                (
-                    queen_bee_movement
-                        .after(gamepad_system)
-                        .run_if(in_state(GameState::Playing)),
-                    worker_bee_movement
-                        .after(gamepad_system)
-                        .run_if(in_state(GameState::Playing)),
-                ),
+                FixedUpdate,
+                (queen_bee_movement, worker_bee_movement)
+                    .after(InputSet)
+                    .before(PhysicsSet::Prepare)
+                    .run_if(in_state(GameState::Playing)),
             )
             .add_systems(
                 PostProcessCollisions,
-                flower_collision.run_if(in_state(GameState::Playing)),
+                hazard_collision.run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -45,6 +49,10 @@ pub struct QueenBee;
 #[derive(Component)]
 pub struct Production(pub f32);
 
+/// Seconds since the queen produced her last worker bee
+#[derive(Component, Default)]
+pub struct ProductionProgress(pub f32);
+
 #[derive(Component)]
 pub struct WorkerBee;
 
@@ -52,164 +60,258 @@ pub struct WorkerBee;
 pub struct Wall;
 
 #[derive(Component)]
-pub struct Flower;
+pub struct Hazard;
+
+#[derive(PhysicsLayer)]
+pub enum Layer {
+    Queen,
+    Worker,
+    Wall,
+    Flower,
+    Pickup,
+    Wasp,
+    Hazard,
+}
+
+/// The queen collides with everything, unless she is a ghost and passes through worker bees
+pub fn queen_collision_layers(ghost: bool) -> CollisionLayers {
+    if ghost {
+        CollisionLayers::new(
+            [Layer::Queen],
+            [
+                Layer::Wall,
+                Layer::Flower,
+                Layer::Pickup,
+                Layer::Wasp,
+                Layer::Hazard,
+            ],
+        )
+    } else {
+        CollisionLayers::new(
+            [Layer::Queen],
+            [
+                Layer::Worker,
+                Layer::Wall,
+                Layer::Flower,
+                Layer::Pickup,
+                Layer::Wasp,
+                Layer::Hazard,
+            ],
+        )
+    }
+}
 
 fn setup(
     mut commands: Commands,
     textures: Res<TextureAssets>,
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
 ) {
-    // commands.spawn(Camera2dBundle::default());
+    spawn_queen(
+        &mut commands,
+        &textures,
+        &difficulty,
+        &tuning,
+        layout.queen_spawn,
+    );
+    spawn_arena(&mut commands, &textures, &tuning, &layout);
+}
 
-    let square_sprite = Sprite {
-        color: Color::rgb(0.4, 0.4, 0.5),
-        custom_size: Some(Vec2::splat(50.0)),
-        ..default()
-    };
+/// Spawns the queen bee, the arena editor previews her spawn with it
+pub(crate) fn spawn_queen(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    difficulty: &Difficulty,
+    tuning: &Tuning,
+    position: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position.extend(1.))),
+            RigidBody::Dynamic,
+            Collider::ball(tuning.queen_radius as Scalar),
+            LockedAxes::ROTATION_LOCKED,
+            queen_collision_layers(false),
+            CollidingEntities::default(),
+            Production(difficulty.settings.starting_production),
+            ProductionProgress::default(),
+            Lives(tuning.starting_lives),
+            Interpolated::new(position),
+            QueenBee,
+            RoundEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn(bee_visual_bundle(
+                textures.queen.clone(),
+                AnimationSheet::Queen,
+            ));
+        })
+        .id()
+}
+
+/// Spawns the walls around the arena and the walls and hazards of the layout. Rounds and the arena
+/// editor both build the arena with it, so the editor shows exactly what is played.
+pub(crate) fn spawn_arena(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    tuning: &Tuning,
+    layout: &ArenaLayout,
+) -> Vec<Entity> {
+    let wall_thickness = tuning.wall_thickness;
+    let wall_offset = (tuning.arena_size + wall_thickness) / 2.0;
+    let view_size = tuning.view_size();
+    let borders = [
+        // Ceiling
+        ArenaRect::new(
+            Vec2::new(0.0, wall_offset.y),
+            Vec2::new(view_size.x, wall_thickness),
+        ),
+        // Floor
+        ArenaRect::new(
+            Vec2::new(0.0, -wall_offset.y),
+            Vec2::new(view_size.x, wall_thickness),
+        ),
+        // Left wall
+        ArenaRect::new(
+            Vec2::new(-wall_offset.x, 0.0),
+            Vec2::new(wall_thickness, view_size.y),
+        ),
+        // Right wall
+        ArenaRect::new(
+            Vec2::new(wall_offset.x, 0.0),
+            Vec2::new(wall_thickness, view_size.y),
+        ),
+    ];
+
+    let walls = borders
+        .iter()
+        .chain(&layout.walls)
+        .map(|rect| spawn_wall(commands, rect));
+    let hazards = layout
+        .hazards
+        .iter()
+        .map(|rect| spawn_hazard(commands, textures, rect));
+    walls.chain(hazards).collect()
+}
 
-    // Queen Bee
-    commands.spawn((
-        SpriteBundle {
-            texture: textures.queen.clone(),
-            transform: Transform::from_translation(Vec3::new(-350., 0., 1.)),
-            ..Default::default()
-        },
-        RigidBody::Dynamic,
-        Collider::ball(30.0 as Scalar),
-        LockedAxes::ROTATION_LOCKED,
-        Production(1.0),
-        QueenBee,
-    ));
-
-    // Flower
-    commands.spawn((
-        SpriteBundle {
-            texture: textures.flower.clone(),
-            transform: Transform::from_translation(Vec3::new(350., 5., 1.)),
-            ..Default::default()
-        },
-        RigidBody::Kinematic,
-        Collider::ball(20.0 as Scalar),
-        AngularVelocity(1.5),
-        Flower,
-    ));
-
-    // Ceiling
-    commands.spawn((
-        SpriteBundle {
-            sprite: square_sprite.clone(),
-            transform: Transform::from_xyz(0.0, 50.0 * 6.0, 0.0)
-                .with_scale(Vec3::new(17.71, 1.0, 1.0)),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::cuboid(50.0, 50.0),
-    ));
-    // Floor
-    commands.spawn((
-        SpriteBundle {
-            sprite: square_sprite.clone(),
-            transform: Transform::from_xyz(0.0, -53.0 * 6.0, 0.0)
-                .with_scale(Vec3::new(17.71, 1.0, 1.0)),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::cuboid(50.0, 50.0),
-    ));
-    // Left wall
-    commands.spawn((
-        SpriteBundle {
-            sprite: square_sprite.clone(),
-            transform: Transform::from_xyz(-44.0 * 9.5, 0.0, 0.0)
-                .with_scale(Vec3::new(1.0, 12.0, 1.0)),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::cuboid(50.0, 50.0),
-    ));
-    // Right wall
-    commands.spawn((
-        SpriteBundle {
-            sprite: square_sprite,
-            transform: Transform::from_xyz(44.0 * 9.5, 0.0, 0.0)
-                .with_scale(Vec3::new(1.0, 12.0, 1.0)),
-            ..default()
-        },
-        RigidBody::Static,
-        Collider::cuboid(50.0, 50.0),
-    ));
+fn spawn_wall(
+    commands: &mut Commands,
+    rect: &ArenaRect,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.4, 0.4, 0.5),
+                    custom_size: Some(rect.size),
+                    ..default()
+                },
+                transform: Transform::from_translation(rect.center.extend(0.0)),
+                ..default()
+            },
+            RigidBody::Static,
+            Collider::cuboid(rect.size.x as Scalar, rect.size.y as Scalar),
+            CollisionLayers::new([Layer::Wall], [Layer::Queen, Layer::Worker, Layer::Wasp]),
+            Wall,
+            RoundEntity,
+        ))
+        .id()
 }
 
-const QUEEN_MOVEMENT_SCALING_X: f32 = 700.0;
-const QUEEN_MOVEMENT_SCALING_Y: f32 = 700.0;
-const BEE_MOVEMENT_SCALING_X: f32 = 1000.0;
-const BEE_MOVEMENT_SCALING_Y: f32 = 1000.0;
+/// The spikes sprite reaches a bit past the collider
+const HAZARD_SPRITE_SCALING: f32 = 1.2;
+
+fn spawn_hazard(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    rect: &ArenaRect,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(rect.size * HAZARD_SPRITE_SCALING),
+                    ..default()
+                },
+                texture: textures.spikes.clone(),
+                transform: Transform::from_translation(rect.center.extend(0.5)),
+                ..default()
+            },
+            RigidBody::Static,
+            Collider::cuboid(rect.size.x as Scalar, rect.size.y as Scalar),
+            Sensor,
+            CollisionLayers::new([Layer::Hazard], [Layer::Queen]),
+            Hazard,
+            RoundEntity,
+        ))
+        .id()
+}
 
 fn queen_bee_movement(
     time: Res<Time>,
     actions: Res<Actions>,
-    mut bees: Query<(&mut LinearVelocity, &mut Sprite), With<QueenBee>>,
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
+    effects: Query<&TimedEffect>,
+    mut bees: Query<&mut LinearVelocity, With<QueenBee>>,
 ) {
     // Precision is adjusted so that the example works with
     // both the `f32` and `f64` features. Otherwise you don't need this.
     let delta_time = time.delta_seconds_f64().adjust_precision();
+    let boost = if effect_active(&effects, EffectKind::SpeedBoost) {
+        tuning.pickups.speed_boost_scaling
+    } else {
+        1.0
+    };
+    let scaling = difficulty.settings.queen_movement_scaling * boost;
 
-    for (mut linear_velocity, mut sprite) in &mut bees {
-        linear_velocity.x += actions.p1_movement.x * QUEEN_MOVEMENT_SCALING_X * delta_time;
-        linear_velocity.y += actions.p1_movement.y * QUEEN_MOVEMENT_SCALING_Y * delta_time;
-        sprite.flip_x = actions.p1_movement.x < 0.0;
+    for mut linear_velocity in &mut bees {
+        linear_velocity.x += actions.p1_movement.x * scaling.x * delta_time;
+        linear_velocity.y += actions.p1_movement.y * scaling.y * delta_time;
     }
 }
 
 fn worker_bee_movement(
     time: Res<Time>,
     actions: Res<Actions>,
+    difficulty: Res<Difficulty>,
+    effects: Query<&TimedEffect>,
     mut bees: Query<&mut LinearVelocity, With<WorkerBee>>,
 ) {
     // Precision is adjusted so that the example works with
     // both the `f32` and `f64` features. Otherwise you don't need this.
     let delta_time = time.delta_seconds_f64().adjust_precision();
 
+    if effect_active(&effects, EffectKind::Freeze) {
+        for mut linear_velocity in &mut bees {
+            linear_velocity.0 = Vector::ZERO;
+        }
+        return;
+    }
+
+    let scaling = difficulty.settings.bee_movement_scaling;
     for mut linear_velocity in &mut bees {
-        linear_velocity.x += actions.p1_bee_movement.x * BEE_MOVEMENT_SCALING_X * delta_time;
-        linear_velocity.y += actions.p1_bee_movement.y * BEE_MOVEMENT_SCALING_Y * delta_time;
+        linear_velocity.x += actions.p1_bee_movement.x * scaling.x * delta_time;
+        linear_velocity.y += actions.p1_bee_movement.y * scaling.y * delta_time;
     }
 }
 
-pub fn flower_collision(
-    mut actions: ResMut<Actions>,
-    mut score: ResMut<Score>,
-    mut collision_event_reader: EventReader<CollisionStarted>,
-    mut queen_query: Query<(&mut Production, Entity), With<QueenBee>>,
-    mut flower_query: Query<(&mut Transform, Entity), With<Flower>>,
-    time: Res<Time>,
+/// Checks every step rather than only when the contact starts, so a queen that is still on a
+/// hazard when her invulnerability runs out is hurt again
+fn hazard_collision(
+    mut damage_events: EventWriter<QueenDamaged>,
+    queen_query: Query<&CollidingEntities, (With<QueenBee>, Without<Invulnerable>)>,
+    hazard_query: Query<(), With<Hazard>>,
 ) {
-    for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
-        let mut flower_gotten: bool = false;
-
-        if let Ok(_) = queen_query.get(*entity1) {
-            if let Ok(_) = flower_query.get(*entity2) {
-                flower_gotten = true;
-            }
-        } else if let Ok(_) = queen_query.get(*entity2) {
-            if let Ok(_) = flower_query.get(*entity1) {
-                flower_gotten = true;
-            }
-        }
-
-        if flower_gotten {
-            score.points = score.points + 1.0;
-            actions.flower_gotten = true;
-            for (mut transform, _) in &mut flower_query {
-                let randomish = 10000.0 * time.elapsed_seconds();
-                transform.translation.x =
-                    randomish % 370.0 * (-(randomish % 2.0) * 2.0 + 1.0).clamp(-1.0, 1.0);
-                transform.translation.y =
-                    randomish % 210.0 * (-(randomish % 3.0) * 2.0 + 3.0).clamp(-1.0, 1.0);
-            }
-            for (mut production, _) in &mut queen_query {
-                production.0 += 1.0;
-            }
-            break;
+    for colliding in &queen_query {
+        if colliding
+            .iter()
+            .any(|entity| hazard_query.contains(*entity))
+        {
+            damage_events.send(QueenDamaged {
+                source: DamageSource::Hazard,
+            });
         }
     }
 }
@@ -0,0 +1,235 @@
+//! Plays rounds with the scripted bot as fast as possible and writes a summary for balancing
+//!
+//! cargo run --release --bin balance -- --rounds 1000 --difficulty hard --out balance
+//!
+//! Every round gets its own seed, counting up from `--seed`, so two runs with the same options
+//! play the same rounds. The results are written to `rounds.csv` and `summary.json` in `--out`.
+
+use bevy::prelude::*;
+use queen_beevy::bot::{BotPlugin, RoundStats};
+use queen_beevy::headless::{headless_app, start_round};
+use queen_beevy::{Difficulty, DifficultyPreset, GameRng, GameState, FIXED_TIMESTEP_HZ};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: balance [--rounds N] [--seconds S] [--difficulty easy|normal|hard] \
+                     [--seed N] [--out DIRECTORY]";
+
+struct Options {
+    rounds: u64,
+    /// Rounds that last longer than this are stopped
+    seconds: f32,
+    difficulty: DifficultyPreset,
+    seed: u64,
+    out: PathBuf,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rounds: 100,
+            seconds: 180.0,
+            difficulty: DifficultyPreset::Normal,
+            seed: 1,
+            out: PathBuf::from("balance"),
+        }
+    }
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--rounds" => options.rounds = value()?.parse().map_err(|_| "Invalid --rounds")?,
+            "--seconds" => options.seconds = value()?.parse().map_err(|_| "Invalid --seconds")?,
+            "--seed" => options.seed = value()?.parse().map_err(|_| "Invalid --seed")?,
+            "--out" => options.out = PathBuf::from(value()?),
+            "--difficulty" => {
+                let name = value()?;
+                options.difficulty = DifficultyPreset::from_name(&name)
+                    .filter(|preset| *preset != DifficultyPreset::Custom)
+                    .ok_or(format!("Unknown difficulty {name}"))?;
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown option {arg}\n{USAGE}")),
+        }
+    }
+    Ok(options)
+}
+
+#[derive(Serialize)]
+struct RoundSummary {
+    round: u64,
+    seed: u64,
+    /// Whether the queen ran out of lives before the time limit
+    game_over: bool,
+    seconds: f32,
+    flowers_per_minute: f32,
+    #[serde(flatten)]
+    stats: RoundStats,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    difficulty: DifficultyPreset,
+    rounds: usize,
+    mean_seconds: f32,
+    mean_points: f32,
+    mean_flowers_per_minute: f32,
+    mean_max_bees: f32,
+    game_over_rounds: usize,
+    gridlocked_rounds: usize,
+    mean_gridlock_time: Option<f32>,
+    round_results: Vec<RoundSummary>,
+}
+
+fn play_round(
+    options: &Options,
+    round: u64,
+) -> RoundSummary {
+    let seed = options.seed + round;
+    let mut app = headless_app();
+    app.add_plugins(BotPlugin);
+    app.world
+        .resource_mut::<Difficulty>()
+        .select(options.difficulty);
+    app.world.resource_mut::<GameRng>().fixed_seed = Some(seed);
+    start_round(&mut app);
+
+    let max_steps = (options.seconds * FIXED_TIMESTEP_HZ as f32) as u32;
+    let mut game_over = false;
+    while app.world.resource::<RoundStats>().steps < max_steps {
+        app.update();
+        if *app.world.resource::<State<GameState>>().get() == GameState::GameOver {
+            game_over = true;
+            break;
+        }
+    }
+
+    let stats = app.world.resource::<RoundStats>().clone();
+    RoundSummary {
+        round,
+        seed,
+        game_over,
+        seconds: stats.seconds(),
+        flowers_per_minute: stats.flowers_per_minute(),
+        stats,
+    }
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+fn summarize(
+    options: &Options,
+    round_results: Vec<RoundSummary>,
+) -> Summary {
+    let results = &round_results;
+    Summary {
+        difficulty: options.difficulty,
+        rounds: results.len(),
+        mean_seconds: mean(results.iter().map(|round| round.seconds)).unwrap_or_default(),
+        mean_points: mean(results.iter().map(|round| round.stats.points)).unwrap_or_default(),
+        mean_flowers_per_minute: mean(results.iter().map(|round| round.flowers_per_minute))
+            .unwrap_or_default(),
+        mean_max_bees: mean(results.iter().map(|round| round.stats.max_bees as f32))
+            .unwrap_or_default(),
+        game_over_rounds: results.iter().filter(|round| round.game_over).count(),
+        gridlocked_rounds: results
+            .iter()
+            .filter(|round| round.stats.gridlock_time.is_some())
+            .count(),
+        mean_gridlock_time: mean(results.iter().filter_map(|round| round.stats.gridlock_time)),
+        round_results,
+    }
+}
+
+fn to_csv(rounds: &[RoundSummary]) -> String {
+    let mut csv = String::from(
+        "round,seed,game_over,seconds,points,flowers,flowers_per_minute,max_bees,gridlock_time,bee_curve\n",
+    );
+    for round in rounds {
+        let bee_curve: Vec<String> = round
+            .stats
+            .bee_curve
+            .iter()
+            .map(|bees| bees.to_string())
+            .collect();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{:.2},{},{},{:.3},{},{},{}",
+            round.round,
+            round.seed,
+            round.game_over,
+            round.seconds,
+            round.stats.points,
+            round.stats.flowers,
+            round.flowers_per_minute,
+            round.stats.max_bees,
+            round
+                .stats
+                .gridlock_time
+                .map_or(String::new(), |time| format!("{time:.2}")),
+            bee_curve.join(" ")
+        );
+    }
+    csv
+}
+
+fn write_results(
+    options: &Options,
+    summary: &Summary,
+) -> Result<(), String> {
+    std::fs::create_dir_all(&options.out).map_err(|error| error.to_string())?;
+    std::fs::write(
+        options.out.join("rounds.csv"),
+        to_csv(&summary.round_results),
+    )
+    .map_err(|error| error.to_string())?;
+    let json = serde_json::to_string_pretty(summary).map_err(|error| error.to_string())?;
+    std::fs::write(options.out.join("summary.json"), json).map_err(|error| error.to_string())
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut results = Vec::new();
+    for round in 0..options.rounds {
+        results.push(play_round(&options, round));
+        if (round + 1) % 50 == 0 {
+            eprintln!("{}/{} rounds", round + 1, options.rounds);
+        }
+    }
+    let summary = summarize(&options, results);
+
+    println!(
+        "{} rounds on {}: {:.1} s, {:.1} points, {:.2} flowers per minute, {:.1} max bees, \
+         {} game overs, {} gridlocked",
+        summary.rounds,
+        summary.difficulty.name(),
+        summary.mean_seconds,
+        summary.mean_points,
+        summary.mean_flowers_per_minute,
+        summary.mean_max_bees,
+        summary.game_over_rounds,
+        summary.gridlocked_rounds
+    );
+    if let Err(error) = write_results(&options, &summary) {
+        eprintln!(
+            "Failed to write results to {}: {error}",
+            options.out.display()
+        );
+        std::process::exit(1);
+    }
+}
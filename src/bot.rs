@@ -0,0 +1,175 @@
+//! A scripted player for balancing simulations, see the `balance` binary
+//!
+//! The bot steers the queen toward the closest flower while keeping away from wasps and spikes,
+//! and pushes the swarm of worker bees away from her. [`RoundStats`] are collected on the way.
+
+#![allow(clippy::unnecessary_cast)]
+
+use crate::actions::{Actions, InputSet};
+use crate::bees::{Hazard, QueenBee, WorkerBee};
+use crate::events::FlowerCollected;
+use crate::flowers::Flower;
+use crate::wasps::Wasp;
+use crate::{GameState, FIXED_TIMESTEP_HZ};
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use serde::Serialize;
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<RoundStats>()
+            .add_systems(OnEnter(GameState::Playing), reset_stats)
+            .add_systems(
+                FixedUpdate,
+                (play.in_set(InputSet), collect_stats.after(PhysicsSet::Sync))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Speed the bot wants the queen to fly at
+const CRUISE_SPEED: f32 = 300.0;
+const WASP_AVOID_RANGE: f32 = 160.0;
+const HAZARD_AVOID_RANGE: f32 = 100.0;
+const AVOID_WEIGHT: f32 = 2.0;
+/// Fixed steps between two samples of the worker bee curve
+const BEE_SAMPLE_STEPS: u32 = FIXED_TIMESTEP_HZ as u32 * 5;
+/// The queen is gridlocked once her speed stays below this for [`GRIDLOCK_WINDOW`] steps
+const GRIDLOCK_SPEED: f32 = 30.0;
+const GRIDLOCK_WINDOW: u32 = FIXED_TIMESTEP_HZ as u32 * 3;
+
+#[derive(Resource, Default, Clone, Debug, Serialize)]
+pub struct RoundStats {
+    /// Fixed steps since the round started
+    pub steps: u32,
+    pub flowers: u32,
+    pub points: f32,
+    /// Number of worker bees every five seconds
+    pub bee_curve: Vec<usize>,
+    pub max_bees: usize,
+    /// Seconds into the round when the swarm first held the queen in place
+    pub gridlock_time: Option<f32>,
+    #[serde(skip)]
+    slow_steps: u32,
+}
+
+impl RoundStats {
+    pub fn seconds(&self) -> f32 {
+        self.steps as f32 / FIXED_TIMESTEP_HZ as f32
+    }
+
+    pub fn flowers_per_minute(&self) -> f32 {
+        if self.steps == 0 {
+            return 0.0;
+        }
+        self.flowers as f32 * 60.0 / self.seconds()
+    }
+}
+
+fn reset_stats(mut stats: ResMut<RoundStats>) {
+    *stats = RoundStats::default();
+}
+
+fn closest(
+    from: Vec2,
+    positions: impl Iterator<Item = Vec2>,
+) -> Option<Vec2> {
+    positions.min_by(|a, b| {
+        a.distance_squared(from)
+            .total_cmp(&b.distance_squared(from))
+    })
+}
+
+/// Points away from `from`, stronger the closer it is
+fn avoid(
+    position: Vec2,
+    from: Vec2,
+    range: f32,
+) -> Vec2 {
+    let distance = position.distance(from);
+    if distance >= range {
+        return Vec2::ZERO;
+    }
+    (position - from).normalize_or_zero() * (1.0 - distance / range) * AVOID_WEIGHT
+}
+
+fn play(
+    mut actions: ResMut<Actions>,
+    queen_query: Query<(&Transform, &LinearVelocity), With<QueenBee>>,
+    flowers: Query<&Transform, With<Flower>>,
+    wasps: Query<&Transform, With<Wasp>>,
+    hazards: Query<&Transform, With<Hazard>>,
+    workers: Query<&Transform, With<WorkerBee>>,
+) {
+    let Ok((queen_transform, linear_velocity)) = queen_query.get_single() else {
+        return;
+    };
+    let queen = queen_transform.translation.truncate();
+    let velocity = Vec2::new(linear_velocity.x as f32, linear_velocity.y as f32);
+
+    let target = closest(
+        queen,
+        flowers
+            .iter()
+            .map(|transform| transform.translation.truncate()),
+    );
+    let mut desired = target.map_or(Vec2::ZERO, |target| (target - queen).normalize_or_zero());
+    for transform in &wasps {
+        desired += avoid(queen, transform.translation.truncate(), WASP_AVOID_RANGE);
+    }
+    for transform in &hazards {
+        desired += avoid(queen, transform.translation.truncate(), HAZARD_AVOID_RANGE);
+    }
+    // Accelerate toward the desired velocity, which also brakes before reaching a flower
+    let steering = (desired.normalize_or_zero() * CRUISE_SPEED - velocity) / CRUISE_SPEED;
+    actions.p1_movement = steering.clamp_length_max(1.0);
+
+    let worker_count = workers.iter().count();
+    actions.p1_bee_movement = if worker_count == 0 {
+        Vec2::ZERO
+    } else {
+        let swarm = workers
+            .iter()
+            .map(|transform| transform.translation.truncate())
+            .sum::<Vec2>()
+            / worker_count as f32;
+        (swarm - queen).normalize_or_zero()
+    };
+}
+
+fn collect_stats(
+    mut stats: ResMut<RoundStats>,
+    mut collected_events: EventReader<FlowerCollected>,
+    queen_query: Query<&LinearVelocity, With<QueenBee>>,
+    workers: Query<(), With<WorkerBee>>,
+) {
+    for collected in collected_events.read() {
+        stats.flowers += 1;
+        stats.points += collected.score_delta;
+    }
+
+    let bees = workers.iter().count();
+    stats.max_bees = stats.max_bees.max(bees);
+    if stats.steps % BEE_SAMPLE_STEPS == 0 {
+        stats.bee_curve.push(bees);
+    }
+
+    let speed = queen_query
+        .get_single()
+        .map_or(0.0, |velocity| velocity.length() as f32);
+    stats.slow_steps = if speed < GRIDLOCK_SPEED {
+        stats.slow_steps + 1
+    } else {
+        0
+    };
+    if stats.gridlock_time.is_none() && stats.slow_steps >= GRIDLOCK_WINDOW {
+        let steps = (stats.steps + 1).saturating_sub(GRIDLOCK_WINDOW);
+        stats.gridlock_time = Some(steps as f32 / FIXED_TIMESTEP_HZ as f32);
+    }
+    stats.steps += 1;
+}
@@ -0,0 +1,196 @@
+use crate::bees::{QueenBee, WorkerBee};
+use crate::events::{DeathCause, FlowerCollected, QueenHit, WorkerBeeDied};
+use crate::interpolation::Interpolated;
+use crate::tuning::Tuning;
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::window::PrimaryWindow;
+use rand::prelude::*;
+
+pub struct CameraPlugin;
+
+/// This plugin spawns and drives the game camera. The projection always fits the whole arena,
+/// walls included, and adds extra space on the longer axis for other aspect ratios. The UI is
+/// scaled along with it, so the HUD keeps the same size relative to the arena.
+///
+/// Gameplay systems send [`CameraShake`] events to shake the screen. The camera also leans towards
+/// the queen, and zooms out as the swarm grows.
+impl Plugin for CameraPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_event::<CameraShake>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(Update, fit_view.run_if(resource_changed::<Tuning>()))
+            .add_systems(
+                Update,
+                (
+                    shake_on_gameplay_events,
+                    add_trauma,
+                    zoom_camera,
+                    move_camera,
+                )
+                    .chain(),
+            )
+            .add_systems(Update, scale_ui);
+    }
+}
+
+/// The window size, in logical pixels, that the UI layout was made for
+const UI_REFERENCE_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+
+const TRAUMA_DECAY: f32 = 1.5;
+const MAX_SHAKE_OFFSET: f32 = 20.0;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+const FOLLOW_SPEED: f32 = 4.0;
+/// The camera leans this fraction of the way from the arena center towards the queen, so the walls
+/// next to her stay in view
+const FOLLOW_LEAD: f32 = 0.2;
+const ZOOM_SPEED: f32 = 1.0;
+/// Every this many worker bees the camera zooms out by 10%
+const BEES_PER_ZOOM_STEP: f32 = 50.0;
+const MAX_ZOOM: f32 = 1.5;
+
+/// Requests a screen shake, trauma between 0 and 1 adds up and decays over time
+#[derive(Event)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+#[derive(Component, Default)]
+pub struct CameraController {
+    trauma: f32,
+    /// Where the camera looks at before shaking
+    focus: Vec2,
+    zoom: f32,
+}
+
+impl CameraController {
+    /// Where the camera looks at, without the shake
+    pub fn focus(&self) -> Vec2 {
+        self.focus
+    }
+}
+
+fn setup_camera(
+    mut commands: Commands,
+    tuning: Res<Tuning>,
+) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = view_scaling(&tuning);
+    commands.spawn((
+        camera,
+        CameraController {
+            zoom: 1.0,
+            ..default()
+        },
+    ));
+}
+
+fn view_scaling(tuning: &Tuning) -> ScalingMode {
+    let view_size = tuning.view_size();
+    ScalingMode::AutoMin {
+        min_width: view_size.x,
+        min_height: view_size.y,
+    }
+}
+
+/// Keeps the whole arena in view when its size is tuned
+fn fit_view(
+    tuning: Res<Tuning>,
+    mut projections: Query<&mut OrthographicProjection, With<CameraController>>,
+) {
+    for mut projection in &mut projections {
+        projection.scaling_mode = view_scaling(&tuning);
+    }
+}
+
+fn shake_on_gameplay_events(
+    mut collected_events: EventReader<FlowerCollected>,
+    mut died_events: EventReader<WorkerBeeDied>,
+    mut hit_events: EventReader<QueenHit>,
+    mut shake_events: EventWriter<CameraShake>,
+) {
+    for _ in hit_events.read() {
+        shake_events.send(CameraShake { trauma: 0.6 });
+    }
+    for _ in collected_events.read() {
+        shake_events.send(CameraShake { trauma: 0.3 });
+    }
+    for died in died_events.read() {
+        if died.cause == DeathCause::Wasp {
+            shake_events.send(CameraShake { trauma: 0.1 });
+        }
+    }
+}
+
+fn add_trauma(
+    time: Res<Time>,
+    mut shake_events: EventReader<CameraShake>,
+    mut controllers: Query<&mut CameraController>,
+) {
+    let added: f32 = shake_events.read().map(|shake| shake.trauma).sum();
+    for mut controller in &mut controllers {
+        controller.trauma =
+            (controller.trauma + added - TRAUMA_DECAY * time.delta_seconds()).clamp(0.0, 1.0);
+    }
+}
+
+fn zoom_camera(
+    time: Res<Time>,
+    workers: Query<(), With<WorkerBee>>,
+    mut cameras: Query<(&mut CameraController, &mut OrthographicProjection)>,
+) {
+    let bees = workers.iter().count() as f32;
+    let target_zoom = (1.0 + 0.1 * (bees / BEES_PER_ZOOM_STEP).floor()).min(MAX_ZOOM);
+    let step = (ZOOM_SPEED * time.delta_seconds()).min(1.0);
+    for (mut controller, mut projection) in &mut cameras {
+        controller.zoom += (target_zoom - controller.zoom) * step;
+        projection.scale = controller.zoom;
+    }
+}
+
+fn move_camera(
+    time: Res<Time>,
+    tuning: Res<Tuning>,
+    fixed_time: Res<Time<Fixed>>,
+    queen_query: Query<&Interpolated, With<QueenBee>>,
+    mut cameras: Query<(&mut CameraController, &mut Transform)>,
+) {
+    let queen_position = queen_query.get_single().map_or(Vec2::ZERO, |interpolated| {
+        interpolated.position(&fixed_time)
+    });
+    let mut rng = thread_rng();
+
+    // The view always covers the arena, so following is bounded by the arena rather than the view
+    let bounds = tuning.arena_size / 2.0 * FOLLOW_LEAD;
+    let target = (queen_position * FOLLOW_LEAD).clamp(-bounds, bounds);
+
+    for (mut controller, mut transform) in &mut cameras {
+        let step = (FOLLOW_SPEED * time.delta_seconds()).min(1.0);
+        controller.focus = controller.focus.lerp(target, step);
+
+        let shake = controller.trauma * controller.trauma;
+        let offset = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
+            * MAX_SHAKE_OFFSET
+            * shake;
+        transform.translation.x = controller.focus.x + offset.x;
+        transform.translation.y = controller.focus.y + offset.y;
+        transform.rotation =
+            Quat::from_rotation_z(rng.gen_range(-MAX_SHAKE_ANGLE..MAX_SHAKE_ANGLE) * shake);
+    }
+}
+
+fn scale_ui(
+    windows: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let scale = (window.width() / UI_REFERENCE_SIZE.x).min(window.height() / UI_REFERENCE_SIZE.y);
+    if scale > 0.0 && (ui_scale.0 - scale as f64).abs() > f64::EPSILON {
+        ui_scale.0 = scale as f64;
+    }
+}
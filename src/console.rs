@@ -0,0 +1,577 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::bee_spawner::spawn_worker_bee_at;
+use crate::bees::{Production, QueenBee};
+use crate::events::WorkerBeeSpawned;
+use crate::flowers::Flower;
+use crate::game_rng::GameRng;
+use crate::health::GodMode;
+use crate::loading::TextureAssets;
+use crate::tuning::Tuning;
+use crate::GameState;
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::*;
+use bevy::reflect::{GetPath, ReflectRef};
+use bevy_xpbd_2d::{math::*, prelude::*};
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+pub struct ConsolePlugin;
+
+/// This plugin adds a developer console, toggled with [`TOGGLE_KEY`] and only compiled with the
+/// `dev` feature. The game is paused while it is open. Besides a few cheats, it reads and writes
+/// the fields of every resource that is registered with `#[reflect(Resource)]`, like the
+/// [`Difficulty`](crate::Difficulty) tuning. Tab completes commands, resources and fields, the
+/// arrow keys go through the history.
+impl Plugin for ConsolePlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<Console>()
+            .add_systems(Startup, setup_console)
+            .add_systems(
+                Update,
+                (
+                    toggle_console,
+                    (type_into_console, handle_requests, update_console_text)
+                        .chain()
+                        .run_if(console_open),
+                )
+                    .chain(),
+            );
+    }
+}
+
+const TOGGLE_KEY: KeyCode = KeyCode::Grave;
+/// Lines of output kept in the console
+const MAX_LINES: usize = 200;
+const VISIBLE_LINES: usize = 14;
+
+const COMMANDS: [(&str, &str); 10] = [
+    ("help", "Lists the commands"),
+    ("clear", "Clears the output"),
+    ("spawn_bees N", "Spawns N worker bees at the queen"),
+    ("production X", "Sets the production of the queen"),
+    (
+        "teleport_flower X Y",
+        "Moves the flower closest to the queen",
+    ),
+    ("seed N|random", "Sets the seed of the following rounds"),
+    ("god", "Toggles god mode, the queen ignores all damage"),
+    (
+        "state menu|playing|gameover|replays|editor",
+        "Changes the game state",
+    ),
+    (
+        "get [RESOURCE.FIELD]",
+        "Shows a resource or one of its fields",
+    ),
+    (
+        "set RESOURCE.FIELD VALUE",
+        "Changes a number or boolean field",
+    ),
+];
+
+enum ConsoleRequest {
+    Run(String),
+    Complete,
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    lines: VecDeque<String>,
+    history: Vec<String>,
+    /// Entry of the history shown in the input, `None` while typing a new command
+    history_index: Option<usize>,
+    requests: Vec<ConsoleRequest>,
+}
+
+impl Console {
+    fn print(
+        &mut self,
+        text: &str,
+    ) {
+        for line in text.lines() {
+            if self.lines.len() == MAX_LINES {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(line.to_string());
+        }
+    }
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+pub(crate) fn console_open(console: Res<Console>) -> bool {
+    console.open
+}
+
+fn setup_console(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(20),
+                ..default()
+            },
+            ConsoleRoot,
+        ))
+        .with_children(|children| {
+            children.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+fn toggle_console(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut console: ResMut<Console>,
+    mut time: ResMut<Time<Virtual>>,
+    mut roots: Query<&mut Visibility, With<ConsoleRoot>>,
+) {
+    if !keyboard_input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    console.open = !console.open;
+    if console.open {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+    for mut visibility in &mut roots {
+        *visibility = if console.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn type_into_console(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console: ResMut<Console>,
+) {
+    for character in characters.read() {
+        // The toggle key also types a character
+        if !character.char.is_control() && character.char != '`' {
+            console.input.push(character.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        console.requests.push(ConsoleRequest::Complete);
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.history_index = None;
+        if !line.trim().is_empty() {
+            if console.history.last() != Some(&line) {
+                console.history.push(line.clone());
+            }
+            console.requests.push(ConsoleRequest::Run(line));
+        }
+    }
+
+    let history_len = console.history.len();
+    if keyboard_input.just_pressed(KeyCode::Up) && history_len > 0 {
+        let index = console
+            .history_index
+            .map_or(history_len - 1, |index| index.saturating_sub(1));
+        console.history_index = Some(index);
+        console.input = console.history[index].clone();
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        if let Some(index) = console.history_index {
+            if index + 1 < history_len {
+                console.history_index = Some(index + 1);
+                console.input = console.history[index + 1].clone();
+            } else {
+                console.history_index = None;
+                console.input.clear();
+            }
+        }
+    }
+}
+
+/// Commands need the whole world, so they are run in an exclusive system
+fn handle_requests(world: &mut World) {
+    if world.resource::<Console>().requests.is_empty() {
+        return;
+    }
+    let requests = std::mem::take(&mut world.resource_mut::<Console>().requests);
+    for request in requests {
+        match request {
+            ConsoleRequest::Run(line) => {
+                if line.trim() == "clear" {
+                    world.resource_mut::<Console>().lines.clear();
+                    continue;
+                }
+                let result = run_command(world, &line);
+                let mut console = world.resource_mut::<Console>();
+                console.print(&format!("> {line}"));
+                match result {
+                    Ok(output) | Err(output) => console.print(&output),
+                }
+            }
+            ConsoleRequest::Complete => {
+                let input = world.resource::<Console>().input.clone();
+                let (completed, candidates) = complete(world, &input);
+                let mut console = world.resource_mut::<Console>();
+                if candidates.len() > 1 {
+                    console.print(&candidates.join("  "));
+                }
+                console.input = completed;
+            }
+        }
+    }
+}
+
+fn update_console_text(
+    console: Res<Console>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    let skip = console.lines.len().saturating_sub(VISIBLE_LINES);
+    let mut value = String::new();
+    for line in console.lines.iter().skip(skip) {
+        value.push_str(line);
+        value.push('\n');
+    }
+    value.push_str(&format!("> {}_", console.input));
+    for mut text in &mut text_query {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn argument<T: FromStr>(
+    arguments: &[&str],
+    index: usize,
+    name: &str,
+) -> Result<T, String> {
+    let argument = arguments
+        .get(index)
+        .ok_or(format!("Missing argument {name}"))?;
+    argument
+        .parse()
+        .map_err(|_| format!("Invalid {name} {argument}"))
+}
+
+fn run_command(
+    world: &mut World,
+    line: &str,
+) -> Result<String, String> {
+    let mut words = line.split_whitespace();
+    let Some(command) = words.next() else {
+        return Ok(String::new());
+    };
+    let arguments: Vec<&str> = words.collect();
+    match command {
+        "help" => Ok(COMMANDS
+            .iter()
+            .map(|(usage, description)| format!("{usage} - {description}"))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        "spawn_bees" => spawn_bees(world, argument(&arguments, 0, "N")?),
+        "production" => {
+            let production = argument(&arguments, 0, "X")?;
+            let mut queen_query = world.query_filtered::<&mut Production, With<QueenBee>>();
+            let mut queen = queen_query
+                .get_single_mut(world)
+                .map_err(|_| "There is no queen, start a round first")?;
+            queen.0 = production;
+            Ok(format!("Production set to {production}"))
+        }
+        "teleport_flower" => teleport_flower(
+            world,
+            Vec2::new(argument(&arguments, 0, "X")?, argument(&arguments, 1, "Y")?),
+        ),
+        "seed" => {
+            let seed = match arguments.first() {
+                Some(&"random") => None,
+                _ => Some(argument(&arguments, 0, "N")?),
+            };
+            world.resource_mut::<GameRng>().fixed_seed = seed;
+            Ok(match seed {
+                Some(seed) => format!("The following rounds use seed {seed}"),
+                None => "The following rounds use random seeds".to_string(),
+            })
+        }
+        "god" => {
+            let mut god_mode = world.resource_mut::<GodMode>();
+            god_mode.0 = !god_mode.0;
+            Ok(format!(
+                "God mode {}",
+                if god_mode.0 { "enabled" } else { "disabled" }
+            ))
+        }
+        "state" => {
+            let name = argument::<String>(&arguments, 0, "state")?;
+            let state = match name.to_ascii_lowercase().as_str() {
+                "menu" => GameState::Menu,
+                "playing" => GameState::Playing,
+                "gameover" => GameState::GameOver,
+                "replays" => GameState::Replays,
+                "editor" => GameState::Editor,
+                _ => return Err(format!("Unknown state {name}")),
+            };
+            world
+                .resource_mut::<NextState<GameState>>()
+                .set(state.clone());
+            Ok(format!("Changing to {state:?}"))
+        }
+        "get" => match arguments.first() {
+            Some(path) => get_value(world, path),
+            None => Ok(resource_names(world).join("\n")),
+        },
+        "set" => set_value(
+            world,
+            arguments.first().ok_or("Missing argument RESOURCE.FIELD")?,
+            arguments.get(1).ok_or("Missing argument VALUE")?,
+        ),
+        _ => Err(format!("Unknown command {command}, try help")),
+    }
+}
+
+fn spawn_bees(
+    world: &mut World,
+    count: usize,
+) -> Result<String, String> {
+    let transform = *world
+        .query_filtered::<&Transform, With<QueenBee>>()
+        .get_single(world)
+        .map_err(|_| "There is no queen, start a round first")?;
+    let texture = world.resource::<TextureAssets>().bee.clone();
+    let radius = world.resource::<Tuning>().worker_radius;
+
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    let workers: Vec<Entity> = (0..count)
+        .map(|_| spawn_worker_bee_at(&mut commands, texture.clone(), radius, &transform))
+        .collect();
+    queue.apply(world);
+    for entity in workers {
+        world.send_event(WorkerBeeSpawned {
+            entity,
+            position: transform.translation.truncate(),
+        });
+    }
+    Ok(format!("Spawned {count} worker bees"))
+}
+
+fn teleport_flower(
+    world: &mut World,
+    target: Vec2,
+) -> Result<String, String> {
+    let queen = world
+        .query_filtered::<&Transform, With<QueenBee>>()
+        .get_single(world)
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    let mut flower_query = world.query_filtered::<(&mut Transform, &mut Position), With<Flower>>();
+    let (mut transform, mut position) = flower_query
+        .iter_mut(world)
+        .min_by(|(a, _), (b, _)| {
+            let a = a.translation.truncate().distance_squared(queen);
+            let b = b.translation.truncate().distance_squared(queen);
+            a.total_cmp(&b)
+        })
+        .ok_or("There are no flowers")?;
+    transform.translation.x = target.x;
+    transform.translation.y = target.y;
+    position.0 = Vector::new(target.x as Scalar, target.y as Scalar);
+    Ok(format!("Moved a flower to {target}"))
+}
+
+/// Short names of the resources that can be read and written from the console
+fn resource_names(world: &World) -> Vec<String> {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut names: Vec<String> = registry
+        .iter()
+        .filter(|registration| registration.data::<ReflectResource>().is_some())
+        .map(|registration| {
+            registration
+                .type_info()
+                .type_path_table()
+                .short_path()
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn reflect_resource(
+    world: &World,
+    name: &str,
+) -> Result<ReflectResource, String> {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    registry
+        .iter()
+        .find(|registration| registration.type_info().type_path_table().short_path() == name)
+        .and_then(|registration| registration.data::<ReflectResource>())
+        .cloned()
+        .ok_or(format!("Unknown resource {name}, try get"))
+}
+
+/// Splits `Resource.field.path` into the resource name and the path of the field
+fn split_path(path: &str) -> (&str, &str) {
+    path.split_once('.').unwrap_or((path, ""))
+}
+
+fn get_value(
+    world: &World,
+    path: &str,
+) -> Result<String, String> {
+    let (name, field_path) = split_path(path);
+    let resource = reflect_resource(world, name)?
+        .reflect(world)
+        .ok_or(format!("{name} doesn't exist"))?;
+    let field = if field_path.is_empty() {
+        resource
+    } else {
+        resource
+            .reflect_path(field_path)
+            .map_err(|error| error.to_string())?
+    };
+    Ok(format!("{path} = {field:?}"))
+}
+
+/// Parses `value` into `field` if the field is a `T`
+fn parse_as<T: FromStr + Reflect>(
+    field: &mut dyn Reflect,
+    value: &str,
+) -> Option<Result<(), String>> {
+    let field = field.downcast_mut::<T>()?;
+    Some(
+        value
+            .parse()
+            .map(|parsed| *field = parsed)
+            .map_err(|_| format!("{value} is not a {}", std::any::type_name::<T>())),
+    )
+}
+
+fn set_value(
+    world: &mut World,
+    path: &str,
+    value: &str,
+) -> Result<String, String> {
+    let (name, field_path) = split_path(path);
+    if field_path.is_empty() {
+        return Err("Only fields can be set, like Difficulty.settings.substep_count".to_string());
+    }
+    let reflect_resource = reflect_resource(world, name)?;
+    let mut resource = reflect_resource
+        .reflect_mut(world)
+        .ok_or(format!("{name} doesn't exist"))?;
+    let field = resource
+        .reflect_path_mut(field_path)
+        .map_err(|error| error.to_string())?;
+    parse_as::<f32>(field, value)
+        .or_else(|| parse_as::<f64>(field, value))
+        .or_else(|| parse_as::<u32>(field, value))
+        .or_else(|| parse_as::<u64>(field, value))
+        .or_else(|| parse_as::<usize>(field, value))
+        .or_else(|| parse_as::<i32>(field, value))
+        .or_else(|| parse_as::<bool>(field, value))
+        .unwrap_or_else(|| Err(format!("{path} can't be set from the console")))?;
+    Ok(format!("{path} = {value}"))
+}
+
+/// Completes the last word of `input`, returns the new input and every candidate
+fn complete(
+    world: &World,
+    input: &str,
+) -> (String, Vec<String>) {
+    let (head, word) = match input.rfind(' ') {
+        Some(index) => input.split_at(index + 1),
+        None => ("", input),
+    };
+    let candidates: Vec<String> = if head.is_empty() {
+        COMMANDS
+            .iter()
+            .filter_map(|(usage, _)| usage.split(' ').next())
+            .map(str::to_string)
+            .collect()
+    } else if head == "get " || head == "set " {
+        path_candidates(world, word)
+    } else {
+        Vec::new()
+    };
+    let candidates: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect();
+
+    let completed = match candidates.as_slice() {
+        [] => word.to_string(),
+        [candidate] if head.is_empty() => format!("{candidate} "),
+        [candidate] => candidate.clone(),
+        [first, rest @ ..] => rest.iter().fold(first.clone(), |prefix, candidate| {
+            prefix
+                .chars()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+    };
+    (format!("{head}{completed}"), candidates)
+}
+
+/// Resource names, or the fields of the struct that `path` leads to
+fn path_candidates(
+    world: &World,
+    path: &str,
+) -> Vec<String> {
+    let Some((parent, _)) = path.rsplit_once('.') else {
+        return resource_names(world);
+    };
+    let (name, field_path) = split_path(parent);
+    let Some(resource) = reflect_resource(world, name)
+        .ok()
+        .and_then(|reflect_resource| reflect_resource.reflect(world))
+    else {
+        return Vec::new();
+    };
+    let value = if field_path.is_empty() {
+        Some(resource)
+    } else {
+        resource.reflect_path(field_path).ok()
+    };
+    match value.map(|value| value.reflect_ref()) {
+        Some(ReflectRef::Struct(value)) => (0..value.field_len())
+            .filter_map(|index| value.name_at(index))
+            .map(|field| format!("{parent}.{field}"))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,322 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::arena::ArenaLayout;
+use crate::bees::{QueenBee, WorkerBee};
+use crate::flowers::{flower_area, Flower};
+use crate::particles::Particle;
+use crate::pickups::{pickup_area, Pickup, TimedEffect};
+use crate::tuning::Tuning;
+use crate::wasps::{wasp_area, Wasp};
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy::utils::{get_short_name, Duration, Instant};
+use bevy::window::PrimaryWindow;
+use bevy_xpbd_2d::prelude::*;
+
+pub struct DebugOverlayPlugin;
+
+/// This plugin adds a debug overlay, toggled with [`TOGGLE_KEY`]. It draws colliders, velocities
+/// and spawn zones with gizmos, lists entity counts and the time spent stepping the physics, and
+/// shows the components of the entity under the cursor when clicking.
+impl Plugin for DebugOverlayPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_plugins(FrameTimeDiagnosticsPlugin)
+            .init_resource::<DebugOverlay>()
+            .init_resource::<PhysicsStepTime>()
+            .add_systems(Startup, setup_overlay)
+            .add_systems(
+                FixedUpdate,
+                (
+                    start_physics_timer.before(PhysicsSet::StepSimulation),
+                    stop_physics_timer.after(PhysicsSet::StepSimulation),
+                ),
+            )
+            .add_systems(Update, toggle_overlay)
+            .add_systems(
+                Update,
+                (
+                    draw_colliders,
+                    draw_spawn_zones,
+                    select_entity,
+                    update_stats_text,
+                    update_inspector_text,
+                )
+                    .chain()
+                    .after(toggle_overlay)
+                    .run_if(overlay_enabled),
+            );
+    }
+}
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+/// Velocity arrows are this many seconds of movement long
+const VELOCITY_ARROW_SCALE: f32 = 0.2;
+/// How close to a collider a click has to be to select it
+const PICK_MARGIN: f32 = 4.0;
+
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    pub selected: Option<Entity>,
+}
+
+/// Wall clock time of the last physics step, smoothed over a few frames
+#[derive(Resource, Default)]
+pub struct PhysicsStepTime {
+    started: Option<Instant>,
+    pub last: Duration,
+    pub smoothed: Duration,
+}
+
+#[derive(Component)]
+struct DebugOverlayRoot;
+
+#[derive(Component)]
+struct StatsText;
+
+#[derive(Component)]
+struct InspectorText;
+
+fn overlay_enabled(overlay: Res<DebugOverlay>) -> bool {
+    overlay.enabled
+}
+
+fn setup_overlay(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: 16.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(50.0),
+                    right: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(8.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            DebugOverlayRoot,
+        ))
+        .with_children(|children| {
+            children.spawn((TextBundle::from_section("", text_style.clone()), StatsText));
+            children.spawn((
+                TextBundle::from_section("Click an entity to inspect it", text_style),
+                InspectorText,
+            ));
+        });
+}
+
+fn toggle_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut roots: Query<&mut Visibility, With<DebugOverlayRoot>>,
+) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        overlay.enabled = !overlay.enabled;
+        overlay.selected = None;
+    }
+    // The overlay can also be enabled from the command line
+    if !overlay.is_changed() {
+        return;
+    }
+    for mut visibility in &mut roots {
+        *visibility = if overlay.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn start_physics_timer(mut step_time: ResMut<PhysicsStepTime>) {
+    step_time.started = Some(Instant::now());
+}
+
+fn stop_physics_timer(mut step_time: ResMut<PhysicsStepTime>) {
+    let Some(started) = step_time.started.take() else {
+        return;
+    };
+    step_time.last = started.elapsed();
+    step_time.smoothed = step_time.smoothed.mul_f32(0.9) + step_time.last.mul_f32(0.1);
+}
+
+/// The radius of a circle around the collider, used for picking
+fn collider_radius(collider: &Collider) -> Option<f32> {
+    let shape = collider.shape_scaled();
+    if let Some(ball) = shape.as_ball() {
+        Some(ball.radius as f32)
+    } else {
+        shape
+            .as_cuboid()
+            .map(|cuboid| cuboid.half_extents.norm() as f32)
+    }
+}
+
+fn draw_colliders(
+    mut gizmos: Gizmos,
+    overlay: Res<DebugOverlay>,
+    bodies: Query<(
+        Entity,
+        &Collider,
+        &GlobalTransform,
+        Option<&LinearVelocity>,
+        Has<Sensor>,
+    )>,
+) {
+    for (entity, collider, transform, linear_velocity, sensor) in &bodies {
+        let position = transform.translation().truncate();
+        let color = if overlay.selected == Some(entity) {
+            Color::FUCHSIA
+        } else if sensor {
+            Color::YELLOW
+        } else {
+            Color::LIME_GREEN
+        };
+        let shape = collider.shape_scaled();
+        if let Some(ball) = shape.as_ball() {
+            gizmos.circle_2d(position, ball.radius as f32, color);
+        } else if let Some(cuboid) = shape.as_cuboid() {
+            let (_, rotation, _) = transform.to_scale_rotation_translation();
+            gizmos.rect_2d(
+                position,
+                rotation.to_euler(EulerRot::XYZ).2,
+                Vec2::new(cuboid.half_extents.x as f32, cuboid.half_extents.y as f32) * 2.0,
+                color,
+            );
+        }
+        if let Some(linear_velocity) = linear_velocity {
+            let velocity = Vec2::new(linear_velocity.x as f32, linear_velocity.y as f32);
+            if velocity != Vec2::ZERO {
+                gizmos.ray_2d(position, velocity * VELOCITY_ARROW_SCALE, Color::CYAN);
+            }
+        }
+    }
+}
+
+fn draw_spawn_zones(
+    mut gizmos: Gizmos,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+) {
+    if layout.flower_zones.is_empty() {
+        gizmos.rect_2d(Vec2::ZERO, 0.0, flower_area(&tuning) * 2.0, Color::PINK);
+    }
+    for zone in &layout.flower_zones {
+        gizmos.rect_2d(zone.center, 0.0, zone.size, Color::PINK);
+    }
+    gizmos.rect_2d(Vec2::ZERO, 0.0, pickup_area(&tuning) * 2.0, Color::GOLD);
+    gizmos.rect_2d(Vec2::ZERO, 0.0, wasp_area(&tuning) * 2.0, Color::ORANGE_RED);
+}
+
+fn select_entity(
+    mouse_input: Res<Input<MouseButton>>,
+    mut overlay: ResMut<DebugOverlay>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    bodies: Query<(Entity, &Collider, &GlobalTransform)>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    let Some(point) = cameras.iter().find_map(|(camera, camera_transform)| {
+        camera.viewport_to_world_2d(camera_transform, cursor)
+    }) else {
+        return;
+    };
+
+    // Pick the closest entity whose collider is under the cursor
+    overlay.selected = bodies
+        .iter()
+        .filter_map(|(entity, collider, transform)| {
+            let distance = transform.translation().truncate().distance(point);
+            let radius = collider_radius(collider)?;
+            (distance <= radius + PICK_MARGIN).then_some((entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+}
+
+fn update_stats_text(
+    diagnostics: Res<DiagnosticsStore>,
+    step_time: Res<PhysicsStepTime>,
+    queens: Query<(), With<QueenBee>>,
+    workers: Query<(), With<WorkerBee>>,
+    flowers: Query<(), With<Flower>>,
+    pickups: Query<(), With<Pickup>>,
+    wasps: Query<(), With<Wasp>>,
+    effects: Query<(), With<TimedEffect>>,
+    particles: Query<(), With<Particle>>,
+    bodies: Query<(), With<RigidBody>>,
+    entities: Query<()>,
+    mut texts: Query<&mut Text, With<StatsText>>,
+) {
+    let frame_time = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .map_or_else(|| "-".to_string(), |ms| format!("{ms:.2} ms"));
+    let stats = [
+        ("Entities", entities.iter().count()),
+        ("Rigid bodies", bodies.iter().count()),
+        ("QueenBee", queens.iter().count()),
+        ("WorkerBee", workers.iter().count()),
+        ("Flower", flowers.iter().count()),
+        ("Pickup", pickups.iter().count()),
+        ("TimedEffect", effects.iter().count()),
+        ("Wasp", wasps.iter().count()),
+        ("Particle", particles.iter().count()),
+    ];
+
+    let mut value = format!(
+        "Frame time: {frame_time}\nPhysics step: {:.2} ms\n",
+        step_time.smoothed.as_secs_f64() * 1000.0
+    );
+    for (name, count) in stats {
+        value.push_str(&format!("{name}: {count}\n"));
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = value.clone();
+    }
+}
+
+/// Runs with access to the whole world to list the components of the selected entity
+fn update_inspector_text(world: &mut World) {
+    let Some(selected) = world.resource::<DebugOverlay>().selected else {
+        return;
+    };
+    let value = match world.get_entity(selected) {
+        Some(entity) => {
+            let mut names: Vec<String> = world
+                .inspect_entity(entity.id())
+                .into_iter()
+                .map(|info| get_short_name(info.name()))
+                .collect();
+            names.sort();
+            format!("Entity {selected:?}\n{}", names.join("\n"))
+        }
+        None => format!("Entity {selected:?} was despawned"),
+    };
+
+    let mut texts = world.query_filtered::<&mut Text, With<InspectorText>>();
+    for mut text in texts.iter_mut(world) {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,254 @@
+use crate::tuning::present;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct DifficultyPlugin;
+
+/// This plugin owns the [`Difficulty`] resource that gameplay systems read their tuning from
+/// The physics substep count is applied whenever a round starts
+impl Plugin for DifficultyPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<Difficulty>()
+            .register_type::<Difficulty>()
+            .register_type::<DifficultySettings>()
+            .register_type::<DifficultyPreset>()
+            .register_type::<DifficultyPresets>()
+            .add_systems(OnEnter(GameState::Playing), apply_difficulty);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize, Reflect)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl DifficultyPreset {
+    pub const ALL: [DifficultyPreset; 4] = [
+        DifficultyPreset::Easy,
+        DifficultyPreset::Normal,
+        DifficultyPreset::Hard,
+        DifficultyPreset::Custom,
+    ];
+
+    pub fn next(&self) -> DifficultyPreset {
+        let index = DifficultyPreset::ALL
+            .iter()
+            .position(|preset| preset == self)
+            .unwrap_or(0);
+        DifficultyPreset::ALL[(index + 1) % DifficultyPreset::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+            DifficultyPreset::Custom => "Custom",
+        }
+    }
+
+    /// The preset called `name`, ignoring case
+    pub fn from_name(name: &str) -> Option<DifficultyPreset> {
+        DifficultyPreset::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+pub struct DifficultySettings {
+    /// Acceleration of the queen per unit of stick input
+    pub queen_movement_scaling: Vec2,
+    /// Acceleration of the worker bees per unit of stick input
+    pub bee_movement_scaling: Vec2,
+    /// Multiplies the `1.0 / production` seconds between two worker bees
+    pub spawn_interval_scaling: f32,
+    /// [`Production`](crate::bees::Production) of the queen at the start of a round
+    pub starting_production: f32,
+    pub substep_count: u32,
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        DifficultySettings {
+            queen_movement_scaling: Vec2::new(700.0, 700.0),
+            bee_movement_scaling: Vec2::new(1000.0, 1000.0),
+            spawn_interval_scaling: 1.0,
+            starting_production: 1.0,
+            substep_count: 6,
+        }
+    }
+}
+
+/// The settings of every preset but [`DifficultyPreset::Custom`], part of the
+/// [`Tuning`](crate::tuning::Tuning). Fields missing from a preset in the file keep the default of
+/// that preset.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+#[serde(from = "PartialDifficultyPresets")]
+pub struct DifficultyPresets {
+    pub easy: DifficultySettings,
+    pub normal: DifficultySettings,
+    pub hard: DifficultySettings,
+    /// Starting point of [`DifficultyPreset::Custom`], which can only be picked in the menu when
+    /// this is set or the console is there to change it. Missing fields keep the normal values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom: Option<DifficultySettings>,
+}
+
+impl Default for DifficultyPresets {
+    fn default() -> Self {
+        DifficultyPresets {
+            easy: DifficultySettings {
+                queen_movement_scaling: Vec2::new(800.0, 800.0),
+                bee_movement_scaling: Vec2::new(1100.0, 1100.0),
+                spawn_interval_scaling: 1.5,
+                starting_production: 0.75,
+                substep_count: 8,
+            },
+            normal: DifficultySettings::default(),
+            hard: DifficultySettings {
+                queen_movement_scaling: Vec2::new(650.0, 650.0),
+                bee_movement_scaling: Vec2::new(900.0, 900.0),
+                spawn_interval_scaling: 0.7,
+                starting_production: 1.5,
+                substep_count: 4,
+            },
+            custom: None,
+        }
+    }
+}
+
+/// [`DifficultySettings`] as written in the tuning file, where every field is optional
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PartialDifficultySettings {
+    #[serde(deserialize_with = "present")]
+    queen_movement_scaling: Option<Vec2>,
+    #[serde(deserialize_with = "present")]
+    bee_movement_scaling: Option<Vec2>,
+    #[serde(deserialize_with = "present")]
+    spawn_interval_scaling: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    starting_production: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    substep_count: Option<u32>,
+}
+
+impl PartialDifficultySettings {
+    fn or(
+        self,
+        defaults: DifficultySettings,
+    ) -> DifficultySettings {
+        DifficultySettings {
+            queen_movement_scaling: self
+                .queen_movement_scaling
+                .unwrap_or(defaults.queen_movement_scaling),
+            bee_movement_scaling: self
+                .bee_movement_scaling
+                .unwrap_or(defaults.bee_movement_scaling),
+            spawn_interval_scaling: self
+                .spawn_interval_scaling
+                .unwrap_or(defaults.spawn_interval_scaling),
+            starting_production: self
+                .starting_production
+                .unwrap_or(defaults.starting_production),
+            substep_count: self.substep_count.unwrap_or(defaults.substep_count),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PartialDifficultyPresets {
+    easy: PartialDifficultySettings,
+    normal: PartialDifficultySettings,
+    hard: PartialDifficultySettings,
+    #[serde(deserialize_with = "present")]
+    custom: Option<PartialDifficultySettings>,
+}
+
+impl From<PartialDifficultyPresets> for DifficultyPresets {
+    fn from(partial: PartialDifficultyPresets) -> Self {
+        let defaults = DifficultyPresets::default();
+        DifficultyPresets {
+            easy: partial.easy.or(defaults.easy),
+            normal: partial.normal.or(defaults.normal),
+            hard: partial.hard.or(defaults.hard),
+            custom: partial.custom.map(|custom| custom.or(defaults.normal)),
+        }
+    }
+}
+
+impl DifficultyPresets {
+    /// The settings of a preset, `None` for [`DifficultyPreset::Custom`]
+    pub fn get(
+        &self,
+        preset: DifficultyPreset,
+    ) -> Option<DifficultySettings> {
+        match preset {
+            DifficultyPreset::Easy => Some(self.easy),
+            DifficultyPreset::Normal => Some(self.normal),
+            DifficultyPreset::Hard => Some(self.hard),
+            DifficultyPreset::Custom => None,
+        }
+    }
+}
+
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Difficulty {
+    pub preset: DifficultyPreset,
+    pub settings: DifficultySettings,
+    /// Used whenever the custom preset is selected
+    pub custom: DifficultySettings,
+    pub presets: DifficultyPresets,
+}
+
+impl Difficulty {
+    pub fn select(
+        &mut self,
+        preset: DifficultyPreset,
+    ) {
+        self.preset = preset;
+        self.settings = self.presets.get(preset).unwrap_or(self.custom);
+    }
+
+    /// Custom only differs from normal with the `dev` console or a custom preset in the tuning
+    pub fn custom_available(&self) -> bool {
+        cfg!(feature = "dev") || self.presets.custom.is_some()
+    }
+
+    /// The preset after the selected one in the menu, skipping custom when it isn't available
+    pub fn next_preset(&self) -> DifficultyPreset {
+        let next = self.preset.next();
+        if next == DifficultyPreset::Custom && !self.custom_available() {
+            next.next()
+        } else {
+            next
+        }
+    }
+
+    pub fn set_custom(
+        &mut self,
+        settings: DifficultySettings,
+    ) {
+        self.custom = settings;
+        self.select(DifficultyPreset::Custom);
+    }
+}
+
+fn apply_difficulty(
+    difficulty: Res<Difficulty>,
+    mut substep_count: ResMut<SubstepCount>,
+) {
+    substep_count.0 = difficulty.settings.substep_count;
+}
@@ -0,0 +1,63 @@
+use crate::flowers::FlowerKind;
+use bevy::prelude::*;
+
+pub struct GameplayEventsPlugin;
+
+/// This plugin registers the gameplay events. Gameplay systems only report what happened through
+/// them, and everything reacting to it, like the score, sounds, particles or the camera, reads
+/// the events instead of being called from the gameplay code.
+impl Plugin for GameplayEventsPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_event::<FlowerCollected>()
+            .add_event::<WorkerBeeSpawned>()
+            .add_event::<WorkerBeeDied>()
+            .add_event::<QueenHit>()
+            .add_event::<RoundEnded>();
+    }
+}
+
+/// The queen collected a flower, one event per flower even when several are collected at once
+#[derive(Event, Clone, Debug)]
+pub struct FlowerCollected {
+    pub flower: FlowerKind,
+    pub position: Vec2,
+    pub score_delta: f32,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct WorkerBeeSpawned {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeathCause {
+    Wasp,
+    /// Lost to the effect of a collected flower
+    Flower,
+}
+
+/// Sent after the worker bee has been despawned
+#[derive(Event, Clone, Debug)]
+pub struct WorkerBeeDied {
+    pub position: Vec2,
+    pub cause: DeathCause,
+}
+
+/// The queen lost a life, not sent while she is invulnerable
+#[derive(Event, Clone, Debug)]
+pub struct QueenHit {
+    /// Where she was hit, before being sent back to her spawn point
+    pub position: Vec2,
+    pub lives_left: u32,
+}
+
+/// Sent when entering [`GameState::GameOver`](crate::GameState::GameOver) with the final score
+#[derive(Event, Clone, Debug)]
+pub struct RoundEnded {
+    pub points: f32,
+    pub bees: usize,
+}
@@ -0,0 +1,449 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::animation::{AnimationSheet, SpriteAnimation};
+use crate::arena::ArenaLayout;
+use crate::bees::{Layer, Production, QueenBee, WorkerBee};
+use crate::events::{DeathCause, FlowerCollected, WorkerBeeDied};
+use crate::game_rng::GameRng;
+use crate::loading::TextureAssets;
+use crate::tuning::{present, Tuning};
+use crate::wasps::wasp_collision;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy_xpbd_2d::{math::*, prelude::*};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct FlowersPlugin;
+
+/// This plugin keeps a handful of flowers on the field and applies their effects when the queen
+/// collects them. Every flower kind is described by a [`FlowerSpec`] in the [`Tuning`].
+impl Plugin for FlowersPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<FlowerSpawnTimer>()
+            .add_systems(OnEnter(GameState::Playing), setup)
+            .add_systems(
+                FixedUpdate,
+                (spawn_flowers, wilt_flowers)
+                    .before(PhysicsSet::Prepare)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostProcessCollisions,
+                // Worker bees killed by wasps in the same step are despawned before flowers pick theirs
+                flower_collision
+                    .after(wasp_collision)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// The flower sprite is drawn at its native size for this collider radius
+const FLOWER_SPRITE_RADIUS: f32 = 20.0;
+
+/// Half the size of the area that flowers grow in
+pub fn flower_area(tuning: &Tuning) -> Vec2 {
+    tuning.arena_size / 2.0 - tuning.flowers.margin
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlowerKind {
+    Daisy,
+    Sunflower,
+    Lavender,
+    Foxglove,
+    Orchid,
+}
+
+impl FlowerKind {
+    pub const ALL: [FlowerKind; 5] = [
+        FlowerKind::Daisy,
+        FlowerKind::Sunflower,
+        FlowerKind::Lavender,
+        FlowerKind::Foxglove,
+        FlowerKind::Orchid,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            FlowerKind::Daisy => Color::WHITE,
+            FlowerKind::Sunflower => Color::rgb(1.0, 0.85, 0.2),
+            FlowerKind::Lavender => Color::rgb(0.7, 0.5, 1.0),
+            FlowerKind::Foxglove => Color::rgb(1.0, 0.4, 0.7),
+            FlowerKind::Orchid => Color::rgb(0.3, 1.0, 0.9),
+        }
+    }
+
+    fn random(
+        rng: &mut impl Rng,
+        flowers: &FlowerTuning,
+    ) -> FlowerKind {
+        *FlowerKind::ALL
+            .choose_weighted(rng, |kind| flowers.get(*kind).weight)
+            .unwrap_or(&FlowerKind::Daisy)
+    }
+}
+
+/// Everything that makes one kind of flower different from another, part of the [`Tuning`]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+pub struct FlowerSpec {
+    /// Collider radius, the sprite is scaled to match
+    pub radius: f32,
+    /// Angular velocity
+    pub spin: f32,
+    /// Points added to the score on pickup
+    pub points: f32,
+    /// Change to the queen's [`Production`] on pickup, negative values calm the hive
+    pub production: f32,
+    /// Fraction of the worker bees that leave the hive on pickup
+    pub worker_loss: f32,
+    /// Seconds before the flower wilts away on its own, `None` for flowers that stay
+    pub lifetime: Option<f32>,
+    /// Relative chance of this kind being picked when a flower spawns
+    pub weight: u32,
+}
+
+/// How flowers grow and the [`FlowerSpec`] of every kind, part of the [`Tuning`]. Fields missing
+/// from a kind in the file keep the default of that kind.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+#[serde(from = "PartialFlowerTuning")]
+pub struct FlowerTuning {
+    /// No more flowers grow while this many are in the arena
+    pub max_flowers: usize,
+    /// Seconds between two flowers growing
+    pub spawn_interval: f32,
+    /// Collecting flowers never lowers the queen's production below this
+    pub min_production: f32,
+    /// Space kept free between the flowers and the walls
+    pub margin: Vec2,
+    pub daisy: FlowerSpec,
+    pub sunflower: FlowerSpec,
+    pub lavender: FlowerSpec,
+    pub foxglove: FlowerSpec,
+    pub orchid: FlowerSpec,
+}
+
+impl Default for FlowerTuning {
+    fn default() -> Self {
+        FlowerTuning {
+            max_flowers: 3,
+            spawn_interval: 4.0,
+            min_production: 0.25,
+            margin: Vec2::new(30.0, 70.0),
+            daisy: FlowerSpec {
+                radius: 20.0,
+                spin: 1.5,
+                points: 1.0,
+                production: 1.0,
+                worker_loss: 0.0,
+                lifetime: None,
+                weight: 6,
+            },
+            sunflower: FlowerSpec {
+                radius: 26.0,
+                spin: 0.8,
+                points: 5.0,
+                production: 1.0,
+                worker_loss: 0.0,
+                lifetime: None,
+                weight: 2,
+            },
+            lavender: FlowerSpec {
+                radius: 18.0,
+                spin: 2.5,
+                points: 1.0,
+                production: -1.0,
+                worker_loss: 0.0,
+                lifetime: None,
+                weight: 2,
+            },
+            foxglove: FlowerSpec {
+                radius: 18.0,
+                spin: -2.0,
+                points: 1.0,
+                production: 0.0,
+                worker_loss: 0.25,
+                lifetime: None,
+                weight: 1,
+            },
+            orchid: FlowerSpec {
+                radius: 14.0,
+                spin: 4.0,
+                points: 15.0,
+                production: 2.0,
+                worker_loss: 0.0,
+                lifetime: Some(6.0),
+                weight: 1,
+            },
+        }
+    }
+}
+
+impl FlowerTuning {
+    pub fn get(
+        &self,
+        kind: FlowerKind,
+    ) -> FlowerSpec {
+        match kind {
+            FlowerKind::Daisy => self.daisy,
+            FlowerKind::Sunflower => self.sunflower,
+            FlowerKind::Lavender => self.lavender,
+            FlowerKind::Foxglove => self.foxglove,
+            FlowerKind::Orchid => self.orchid,
+        }
+    }
+}
+
+/// [`FlowerSpec`] as written in the tuning file, where every field is optional
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PartialFlowerSpec {
+    #[serde(deserialize_with = "present")]
+    radius: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    spin: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    points: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    production: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    worker_loss: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    lifetime: Option<Option<f32>>,
+    #[serde(deserialize_with = "present")]
+    weight: Option<u32>,
+}
+
+impl PartialFlowerSpec {
+    fn or(
+        self,
+        defaults: FlowerSpec,
+    ) -> FlowerSpec {
+        FlowerSpec {
+            radius: self.radius.unwrap_or(defaults.radius),
+            spin: self.spin.unwrap_or(defaults.spin),
+            points: self.points.unwrap_or(defaults.points),
+            production: self.production.unwrap_or(defaults.production),
+            worker_loss: self.worker_loss.unwrap_or(defaults.worker_loss),
+            lifetime: self.lifetime.unwrap_or(defaults.lifetime),
+            weight: self.weight.unwrap_or(defaults.weight),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PartialFlowerTuning {
+    #[serde(deserialize_with = "present")]
+    max_flowers: Option<usize>,
+    #[serde(deserialize_with = "present")]
+    spawn_interval: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    min_production: Option<f32>,
+    #[serde(deserialize_with = "present")]
+    margin: Option<Vec2>,
+    daisy: PartialFlowerSpec,
+    sunflower: PartialFlowerSpec,
+    lavender: PartialFlowerSpec,
+    foxglove: PartialFlowerSpec,
+    orchid: PartialFlowerSpec,
+}
+
+impl From<PartialFlowerTuning> for FlowerTuning {
+    fn from(partial: PartialFlowerTuning) -> Self {
+        let defaults = FlowerTuning::default();
+        FlowerTuning {
+            max_flowers: partial.max_flowers.unwrap_or(defaults.max_flowers),
+            spawn_interval: partial.spawn_interval.unwrap_or(defaults.spawn_interval),
+            min_production: partial.min_production.unwrap_or(defaults.min_production),
+            margin: partial.margin.unwrap_or(defaults.margin),
+            daisy: partial.daisy.or(defaults.daisy),
+            sunflower: partial.sunflower.or(defaults.sunflower),
+            lavender: partial.lavender.or(defaults.lavender),
+            foxglove: partial.foxglove.or(defaults.foxglove),
+            orchid: partial.orchid.or(defaults.orchid),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Flower {
+    pub kind: FlowerKind,
+}
+
+/// Counts down the remaining life of a timed flower
+#[derive(Component)]
+struct Wilting(Timer);
+
+#[derive(Resource, Default)]
+struct FlowerSpawnTimer(Timer);
+
+fn setup(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+    mut spawn_timer: ResMut<FlowerSpawnTimer>,
+) {
+    spawn_timer.0 = Timer::from_seconds(tuning.flowers.spawn_interval, TimerMode::Repeating);
+    spawn_flower(
+        &mut commands,
+        &textures,
+        &tuning,
+        FlowerKind::Daisy,
+        layout.first_flower,
+    );
+}
+
+pub fn spawn_flower(
+    commands: &mut Commands,
+    textures: &TextureAssets,
+    tuning: &Tuning,
+    kind: FlowerKind,
+    position: Vec2,
+) -> Entity {
+    let spec = tuning.flowers.get(kind);
+    let mut flower = commands.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color: kind.color(),
+                ..default()
+            },
+            texture_atlas: textures.flower.clone(),
+            transform: Transform::from_translation(position.extend(1.))
+                .with_scale(Vec3::splat(spec.radius / FLOWER_SPRITE_RADIUS)),
+            ..Default::default()
+        },
+        RigidBody::Kinematic,
+        Collider::ball(spec.radius as Scalar),
+        AngularVelocity(spec.spin as Scalar),
+        CollisionLayers::new([Layer::Flower], [Layer::Queen, Layer::Worker]),
+        SpriteAnimation::new(AnimationSheet::Flower),
+        Flower { kind },
+        RoundEntity,
+    ));
+    if let Some(lifetime) = spec.lifetime {
+        flower.insert(Wilting(Timer::from_seconds(lifetime, TimerMode::Once)));
+    }
+    flower.id()
+}
+
+/// A point in one of the flower zones of the layout, bigger zones are picked more often
+fn random_flower_position(
+    rng: &mut impl Rng,
+    tuning: &Tuning,
+    layout: &ArenaLayout,
+) -> Vec2 {
+    if let Ok(zone) = layout.flower_zones.choose_weighted(rng, |zone| zone.area()) {
+        return zone.random_point(rng);
+    }
+    let area = flower_area(tuning);
+    Vec2::new(
+        rng.gen_range(-area.x..area.x),
+        rng.gen_range(-area.y..area.y),
+    )
+}
+
+fn spawn_flowers(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+    time: Res<Time>,
+    mut game_rng: ResMut<GameRng>,
+    mut spawn_timer: ResMut<FlowerSpawnTimer>,
+    flowers: Query<(), With<Flower>>,
+) {
+    if !spawn_timer.0.tick(time.delta()).just_finished()
+        || flowers.iter().count() >= tuning.flowers.max_flowers
+    {
+        return;
+    }
+    let rng = game_rng.rng();
+    let kind = FlowerKind::random(rng, &tuning.flowers);
+    let position = random_flower_position(rng, &tuning, &layout);
+    spawn_flower(&mut commands, &textures, &tuning, kind, position);
+}
+
+fn wilt_flowers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flowers: Query<(Entity, &mut Wilting)>,
+) {
+    for (entity, mut wilting) in &mut flowers {
+        if wilting.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn flower_collision(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+    mut game_rng: ResMut<GameRng>,
+    mut collision_event_reader: EventReader<CollisionStarted>,
+    mut collected_events: EventWriter<FlowerCollected>,
+    mut died_events: EventWriter<WorkerBeeDied>,
+    mut queen_query: Query<&mut Production, With<QueenBee>>,
+    flower_query: Query<(&Flower, &Transform)>,
+    worker_query: Query<(Entity, &Transform), With<WorkerBee>>,
+) {
+    let rng = game_rng.rng();
+    let mut collected: Vec<Entity> = Vec::new();
+    let mut killed: Vec<Entity> = Vec::new();
+
+    for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
+        let flower_entity = if queen_query.contains(*entity1) {
+            *entity2
+        } else if queen_query.contains(*entity2) {
+            *entity1
+        } else {
+            continue;
+        };
+        let Ok((flower, flower_transform)) = flower_query.get(flower_entity) else {
+            continue;
+        };
+        if collected.contains(&flower_entity) {
+            continue;
+        }
+        collected.push(flower_entity);
+
+        let spec = tuning.flowers.get(flower.kind);
+        collected_events.send(FlowerCollected {
+            flower: flower.kind,
+            position: flower_transform.translation.truncate(),
+            score_delta: spec.points,
+        });
+        for mut production in &mut queen_query {
+            production.0 = (production.0 + spec.production).max(tuning.flowers.min_production);
+        }
+        if spec.worker_loss > 0.0 {
+            let workers: Vec<(Entity, &Transform)> = worker_query
+                .iter()
+                .filter(|(worker, _)| !killed.contains(worker))
+                .collect();
+            let lost = (workers.len() as f32 * spec.worker_loss).round() as usize;
+            for (worker, transform) in workers.choose_multiple(rng, lost) {
+                killed.push(*worker);
+                commands.entity(*worker).despawn_recursive();
+                died_events.send(WorkerBeeDied {
+                    position: transform.translation.truncate(),
+                    cause: DeathCause::Flower,
+                });
+            }
+        }
+
+        commands.entity(flower_entity).despawn();
+        // Timed flowers are a bonus, every other flower grows back somewhere else
+        if spec.lifetime.is_none() {
+            let kind = FlowerKind::random(rng, &tuning.flowers);
+            let position = random_flower_position(rng, &tuning, &layout);
+            spawn_flower(&mut commands, &textures, &tuning, kind, position);
+        }
+    }
+}
@@ -1,55 +1,208 @@
-use crate::GameState;
+use crate::bees::WorkerBee;
+use crate::debug_overlay::PhysicsStepTime;
 use bevy::{
     diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     prelude::*,
 };
+use bevy_xpbd_2d::prelude::*;
+use std::collections::VecDeque;
 
 #[derive(Default)]
 pub struct FPSCounterPlugin;
 
+/// This plugin shows a performance panel in the bottom right corner, out of the way of the
+/// scoreboard. It is hidden by default and toggled with [`TOGGLE_KEY`] or through the
+/// [`PerformanceHud`] resource.
 impl Plugin for FPSCounterPlugin {
     fn build(
         &self,
         app: &mut App,
     ) {
-        app.add_systems(OnEnter(GameState::Playing), setup)
-            .add_systems(Update, update_fps_text.run_if(in_state(GameState::Playing)));
+        app.init_resource::<PerformanceHud>()
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (
+                    toggle_performance_hud,
+                    record_frame_time,
+                    (update_fps_text, update_frame_time_graph).run_if(hud_enabled),
+                )
+                    .chain(),
+            );
     }
 }
 
+const TOGGLE_KEY: KeyCode = KeyCode::F2;
+/// Number of frames shown in the frame time graph
+const GRAPH_FRAMES: usize = 60;
+const GRAPH_HEIGHT: f32 = 40.0;
+/// Frame times at the top of the graph, in milliseconds
+const GRAPH_MAX_FRAME_TIME: f32 = 50.0;
+
+#[derive(Resource, Default)]
+pub struct PerformanceHud {
+    pub enabled: bool,
+    frame_times: VecDeque<f32>,
+}
+
+#[derive(Component)]
+struct PerformancePanel;
+
 #[derive(Component)]
 struct FpsText;
 
+#[derive(Component)]
+struct FrameTimeBar(usize);
+
+fn hud_enabled(hud: Res<PerformanceHud>) -> bool {
+    hud.enabled
+}
+
 fn setup(mut commands: Commands) {
-    commands.spawn((
-        TextBundle::from_section(
-            "FPS: ",
-            TextStyle {
-                font: default(),
-                font_size: 20.0,
-                color: Color::TOMATO,
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(8.0),
+                    right: Val::Px(8.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
             },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(5.0),
-            left: Val::Px(5.0),
-            ..default()
-        }),
-        FpsText,
-    ));
+            PerformancePanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section(
+                    "FPS: ",
+                    TextStyle {
+                        font: default(),
+                        font_size: 16.0,
+                        color: Color::TOMATO,
+                    },
+                ),
+                FpsText,
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Px(GRAPH_HEIGHT),
+                        align_items: AlignItems::FlexEnd,
+                        column_gap: Val::Px(1.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for index in 0..GRAPH_FRAMES {
+                        graph.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(2.0),
+                                    height: Val::Px(0.0),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            FrameTimeBar(index),
+                        ));
+                    }
+                });
+        });
+}
+
+fn toggle_performance_hud(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut hud: ResMut<PerformanceHud>,
+    mut panels: Query<&mut Visibility, With<PerformancePanel>>,
+) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        hud.enabled = !hud.enabled;
+    }
+    if !hud.is_changed() {
+        return;
+    }
+    for mut visibility in &mut panels {
+        *visibility = if hud.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+fn record_frame_time(
+    time: Res<Time>,
+    mut hud: ResMut<PerformanceHud>,
+) {
+    // Recorded even while hidden, so the graph is already filled when opening the panel
+    let hud = hud.bypass_change_detection();
+    hud.frame_times.push_back(time.delta_seconds() * 1000.0);
+    if hud.frame_times.len() > GRAPH_FRAMES {
+        hud.frame_times.pop_front();
+    }
 }
 
 fn update_fps_text(
     diagnostics: Res<DiagnosticsStore>,
+    step_time: Res<PhysicsStepTime>,
+    substep_count: Res<SubstepCount>,
+    entities: Query<()>,
+    workers: Query<(), With<WorkerBee>>,
     mut query: Query<&mut Text, With<FpsText>>,
 ) {
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let frame_time = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .unwrap_or_default();
+    let physics_time = step_time.smoothed.as_secs_f64() * 1000.0;
+    let substep_time = physics_time / substep_count.0.max(1) as f64;
+
     for mut text in &mut query {
-        if let Some(fps) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
-            if let Some(value) = fps.smoothed() {
-                // Update the value of the second section
-                text.sections[0].value = format!("FPS: {value:.2}");
-            }
+        text.sections[0].value = format!(
+            "FPS: {fps:.0} ({frame_time:.2} ms)\n\
+             Entities: {}\n\
+             Worker bees: {}\n\
+             Physics: {physics_time:.2} ms, {substep_time:.3} ms x {} substeps",
+            entities.iter().count(),
+            workers.iter().count(),
+            substep_count.0,
+        );
+    }
+}
+
+fn update_frame_time_graph(
+    hud: Res<PerformanceHud>,
+    mut bars: Query<(&FrameTimeBar, &mut Style, &mut BackgroundColor)>,
+) {
+    // The newest frame is drawn on the right
+    let offset = GRAPH_FRAMES - hud.frame_times.len();
+    for (bar, mut style, mut background_color) in &mut bars {
+        let frame_time = bar
+            .0
+            .checked_sub(offset)
+            .and_then(|index| hud.frame_times.get(index))
+            .copied()
+            .unwrap_or_default();
+        style.height = Val::Px((frame_time / GRAPH_MAX_FRAME_TIME).min(1.0) * GRAPH_HEIGHT);
+        *background_color = if frame_time <= 1000.0 / 60.0 {
+            Color::LIME_GREEN
+        } else if frame_time <= 1000.0 / 30.0 {
+            Color::YELLOW
+        } else {
+            Color::RED
         }
+        .into();
     }
 }
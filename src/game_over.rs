@@ -0,0 +1,185 @@
+use crate::ghost::{record_personal_best, PersonalBest};
+use crate::high_scores::{record_high_score, HighScores};
+use crate::menu::{ButtonColors, ChangeState};
+use crate::scoreboard::Score;
+use crate::time_attack::{steps_to_seconds, GameMode, TimeAttack};
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+
+pub struct GameOverPlugin;
+
+/// This plugin shows the results of a round once the queen has run out of lives, or the time of
+/// a finished time attack round. Leaving the screen despawns everything that belonged to the round
+impl Plugin for GameOverPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            setup_game_over
+                .after(record_high_score)
+                .after(record_personal_best),
+        )
+        .add_systems(OnExit(GameState::GameOver), cleanup_round);
+    }
+}
+
+#[derive(Component)]
+struct GameOverScreen;
+
+fn setup_game_over(
+    mut commands: Commands,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    game_mode: Res<GameMode>,
+    time_attack: Res<TimeAttack>,
+    personal_best: Res<PersonalBest>,
+    tuning: Res<Tuning>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                ..default()
+            },
+            GameOverScreen,
+        ))
+        .with_children(|children| {
+            children.spawn(
+                TextBundle::from_section(
+                    "Game Over",
+                    TextStyle {
+                        font: default(),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+            children.spawn(
+                TextBundle::from_section(
+                    format!("Score: {:.0}   Worker Bees: {}", score.points, score.bees),
+                    TextStyle {
+                        font: default(),
+                        font_size: 24.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+            if *game_mode == GameMode::TimeAttack {
+                let (result, color) = match time_attack.finished {
+                    Some(steps) if personal_best.improved => (
+                        format!(
+                            "Time: {:.2}   New personal best!",
+                            steps_to_seconds(steps as f32)
+                        ),
+                        Color::rgb(1.0, 0.85, 0.2),
+                    ),
+                    Some(steps) => (
+                        format!("Time: {:.2}", steps_to_seconds(steps as f32)),
+                        Color::rgb(0.9, 0.9, 0.9),
+                    ),
+                    None => (
+                        format!(
+                            "Out of lives before collecting {:.0} points",
+                            tuning.time_attack_points
+                        ),
+                        Color::rgb(0.9, 0.9, 0.9),
+                    ),
+                };
+                children.spawn(TextBundle::from_section(
+                    result,
+                    TextStyle {
+                        font: default(),
+                        font_size: 20.0,
+                        color,
+                    },
+                ));
+            }
+            let table_size = match *game_mode {
+                GameMode::Classic => 5,
+                GameMode::TimeAttack => 0,
+            };
+            for (index, high_score) in high_scores.entries.iter().take(table_size).enumerate() {
+                let color = if high_scores.latest == Some(index) {
+                    Color::rgb(1.0, 0.85, 0.2)
+                } else {
+                    Color::rgb(0.9, 0.9, 0.9)
+                };
+                children.spawn(TextBundle::from_section(
+                    format!(
+                        "{}. {:.0} points, {} bees ({})",
+                        index + 1,
+                        high_score.points,
+                        high_score.bees,
+                        high_score.difficulty.name()
+                    ),
+                    TextStyle {
+                        font: default(),
+                        font_size: 18.0,
+                        color,
+                    },
+                ));
+            }
+            children.spawn(NodeBundle {
+                style: Style {
+                    height: Val::Px(30.0),
+                    ..default()
+                },
+                ..default()
+            });
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(400.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::Menu),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Back to Menu",
+                        TextStyle {
+                            font_size: 32.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn cleanup_round(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<RoundEntity>, With<GameOverScreen>)>>,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,65 @@
+use crate::GameState;
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+pub struct GameRngPlugin;
+
+/// This plugin owns the [`GameRng`] that every gameplay system draws its random numbers from
+/// It is reseeded when a round starts, so a seed and the same input always play out the same round
+impl Plugin for GameRngPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<GameRng>()
+            .add_systems(OnEnter(GameState::Playing), reseed);
+    }
+}
+
+#[derive(Resource)]
+pub struct GameRng {
+    /// Seed of the current round
+    pub seed: u64,
+    /// Seed for every following round, each round picks a random one if this is `None`
+    pub fixed_seed: Option<u64>,
+    rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(None)
+    }
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        GameRng {
+            seed: 0,
+            fixed_seed,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// Restarts the random numbers of the current round from `seed`
+    pub fn reseed_with(
+        &mut self,
+        seed: u64,
+    ) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn reseed(&mut self) {
+        let seed = self.fixed_seed.unwrap_or_else(|| thread_rng().gen());
+        self.reseed_with(seed);
+    }
+}
+
+pub(crate) fn reseed(mut game_rng: ResMut<GameRng>) {
+    game_rng.reseed();
+}
@@ -0,0 +1,247 @@
+use crate::animation::{AnimationSheet, SpriteAnimation};
+use crate::arena::{ArenaLayout, TestPlay};
+use crate::difficulty::Difficulty;
+use crate::loading::TextureAssets;
+use crate::time_attack::{steps_to_seconds, GameMode, PositionTrack, TimeAttack};
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+pub struct GhostPlugin;
+
+/// This plugin keeps the personal best of time attack rounds for every difficulty, tuning and
+/// arena. While chasing it, a translucent ghost queen retraces the best run's [`PositionTrack`]
+/// and the clock is shown next to the best time. A faster run replaces the personal best when it
+/// finishes.
+impl Plugin for GhostPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<PersonalBest>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (select_personal_best, spawn_ghost, spawn_clock)
+                    .chain()
+                    .run_if(resource_equals(GameMode::TimeAttack)),
+            )
+            .add_systems(
+                Update,
+                (move_ghost, update_clock).run_if(
+                    in_state(GameState::Playing).and_then(resource_equals(GameMode::TimeAttack)),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                record_personal_best.run_if(
+                    resource_equals(GameMode::TimeAttack)
+                        .and_then(not(resource_exists::<TestPlay>())),
+                ),
+            );
+    }
+}
+
+const GHOST_COLOR: Color = Color::rgba(0.8, 0.9, 1.0, 0.4);
+
+/// The fastest finished time attack runs, only runs with the same key are compared
+#[derive(Resource, Default)]
+pub struct PersonalBest {
+    tracks: HashMap<String, PositionTrack>,
+    /// Names the difficulty preset, its settings, the tuning and the arena of the current round
+    pub key: String,
+    /// Set when the last run beat the previous best
+    pub improved: bool,
+}
+
+impl PersonalBest {
+    /// The best run with the current key
+    pub fn get(&self) -> Option<&PositionTrack> {
+        self.tracks.get(&self.key)
+    }
+
+    /// Switches to the best run with `key`, loading it the first time it is needed
+    fn select(
+        &mut self,
+        key: String,
+    ) {
+        if !self.tracks.contains_key(&key) {
+            if let Some(track) = PersonalBest::load(&key) {
+                self.tracks.insert(key.clone(), track);
+            }
+        }
+        self.key = key;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn path(key: &str) -> String {
+        format!("personal_best_{key}.ghost")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load(key: &str) -> Option<PositionTrack> {
+        let bytes = std::fs::read(PersonalBest::path(key)).ok()?;
+        PositionTrack::from_bytes(&bytes)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load(_key: &str) -> Option<PositionTrack> {
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) {
+        let Some(track) = self.get() else {
+            return;
+        };
+        if let Err(error) = std::fs::write(PersonalBest::path(&self.key), track.to_bytes()) {
+            warn!("Failed to save personal best {error}");
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) {}
+}
+
+/// The preset name followed by a hash of everything else that changes how a round plays, so that
+/// runs on a custom difficulty, an edited tuning or another arena never race each other
+fn personal_best_key(
+    difficulty: &Difficulty,
+    tuning: &Tuning,
+    layout: &ArenaLayout,
+) -> String {
+    let round = ron::to_string(&(difficulty.settings, tuning, layout)).unwrap_or_default();
+    // FNV-1a, the key has to stay the same across builds to find the saved file again
+    let hash = round.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!(
+        "{}_{hash:016x}",
+        difficulty.preset.name().to_ascii_lowercase()
+    )
+}
+
+fn select_personal_best(
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+    mut personal_best: ResMut<PersonalBest>,
+) {
+    personal_best.select(personal_best_key(&difficulty, &tuning, &layout));
+}
+
+#[derive(Component)]
+struct Ghost;
+
+#[derive(Component)]
+struct ClockText;
+
+/// The ghost only has a sprite, it never collides with anything
+fn spawn_ghost(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    personal_best: Res<PersonalBest>,
+) {
+    let Some(track) = personal_best.get() else {
+        return;
+    };
+    let start = track.position_at(0.0).unwrap_or_default();
+    commands.spawn((
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color: GHOST_COLOR,
+                ..default()
+            },
+            texture_atlas: textures.queen.clone(),
+            transform: Transform::from_translation(start.extend(0.9)),
+            ..default()
+        },
+        SpriteAnimation::new(AnimationSheet::Queen),
+        Ghost,
+        RoundEntity,
+    ));
+}
+
+fn move_ghost(
+    fixed_time: Res<Time<Fixed>>,
+    time_attack: Res<TimeAttack>,
+    personal_best: Res<PersonalBest>,
+    mut ghosts: Query<(&mut Transform, &mut TextureAtlasSprite), With<Ghost>>,
+) {
+    let Some(track) = personal_best.get() else {
+        return;
+    };
+    let steps = time_attack.steps as f32 + fixed_time.overstep_percentage();
+    let Some(position) = track.position_at(steps) else {
+        return;
+    };
+    for (mut transform, mut sprite) in &mut ghosts {
+        let delta = position - transform.translation.truncate();
+        if delta.x.abs() > 0.5 {
+            sprite.flip_x = delta.x < 0.0;
+        }
+        transform.translation = position.extend(transform.translation.z);
+        // The ghost fades out once the best run was over
+        if steps > track.finish_steps as f32 {
+            sprite.color.set_a(0.0);
+        }
+    }
+}
+
+fn spawn_clock(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: default(),
+                font_size: 22.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(30.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        ClockText,
+        RoundEntity,
+    ));
+}
+
+fn update_clock(
+    time_attack: Res<TimeAttack>,
+    personal_best: Res<PersonalBest>,
+    mut text_query: Query<&mut Text, With<ClockText>>,
+) {
+    let time = steps_to_seconds(time_attack.steps as f32);
+    let value = match personal_best.get() {
+        Some(track) => format!(
+            "Time: {time:.2}   Best: {:.2}",
+            steps_to_seconds(track.finish_steps as f32)
+        ),
+        None => format!("Time: {time:.2}"),
+    };
+    for mut text in &mut text_query {
+        text.sections[0].value = value.clone();
+    }
+}
+
+pub fn record_personal_best(
+    time_attack: Res<TimeAttack>,
+    mut personal_best: ResMut<PersonalBest>,
+) {
+    personal_best.improved = false;
+    let Some(finished) = time_attack.finished else {
+        return;
+    };
+    let faster = personal_best
+        .get()
+        .map_or(true, |best| finished < best.finish_steps);
+    if faster {
+        let key = personal_best.key.clone();
+        personal_best.tracks.insert(key, time_attack.track.clone());
+        personal_best.improved = true;
+        personal_best.save();
+    }
+}
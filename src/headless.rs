@@ -0,0 +1,98 @@
+//! Runs the [`GameplayPlugin`] without a window, rendering or audio
+//!
+//! Time advances by exactly one fixed [`TIMESTEP`] on every [`App::update`], so a number of frames
+//! always simulates the same amount of game time. Textures are replaced by placeholder handles.
+
+use crate::arena::ArenaLayout;
+use crate::difficulty::Difficulty;
+use crate::loading::TextureAssets;
+use crate::replay::{Replay, ReplayPlayback};
+use crate::time_attack::GameMode;
+use crate::tuning::Tuning;
+use crate::{GameState, GameplayPlugin, FIXED_TIMESTEP_HZ};
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::Duration;
+
+/// Simulated time per frame, in seconds
+pub const TIMESTEP: f32 = (1.0 / FIXED_TIMESTEP_HZ) as f32;
+
+/// An app with the gameplay core and just enough of Bevy to run it
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        GameplayPlugin,
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / FIXED_TIMESTEP_HZ,
+    )))
+    .insert_resource(TextureAssets::stub());
+    app
+}
+
+/// Skips the loading and menu states and starts a round
+pub fn start_round(app: &mut App) {
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+}
+
+/// Starts a round that plays back `replay` instead of the input in [`Actions`](crate::Actions)
+pub fn start_replay(
+    app: &mut App,
+    replay: Replay,
+) {
+    let playback = app
+        .world
+        .resource_scope(|world, mut difficulty: Mut<Difficulty>| {
+            world.resource_scope(|world, mut game_mode: Mut<GameMode>| {
+                world.resource_scope(|world, mut tuning: Mut<Tuning>| {
+                    ReplayPlayback::new(
+                        replay,
+                        &mut difficulty,
+                        &mut game_mode,
+                        &mut tuning,
+                        &mut world.resource_mut::<ArenaLayout>(),
+                    )
+                })
+            })
+        });
+    app.world.insert_resource(playback);
+    start_round(app);
+}
+
+pub fn run_frames(
+    app: &mut App,
+    frames: usize,
+) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+/// Runs frames until `condition` holds, returns `false` if it didn't within `max_frames`
+pub fn run_until(
+    app: &mut App,
+    max_frames: usize,
+    mut condition: impl FnMut(&mut World) -> bool,
+) -> bool {
+    for _ in 0..max_frames {
+        app.update();
+        if condition(&mut app.world) {
+            return true;
+        }
+    }
+    false
+}
+
+/// The number of entities with the component `T`
+pub fn count<T: Component>(app: &mut App) -> usize {
+    app.world
+        .query_filtered::<(), With<T>>()
+        .iter(&app.world)
+        .count()
+}
@@ -0,0 +1,127 @@
+use crate::arena::ArenaLayout;
+use crate::bees::QueenBee;
+use crate::events::QueenHit;
+use crate::tuning::Tuning;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_xpbd_2d::{math::*, prelude::*};
+
+pub struct HealthPlugin;
+
+/// This plugin applies [`QueenDamaged`] events to the queen's [`Lives`]. A hit costs a life and
+/// sends the queen back to her spawn point, blinking and invulnerable for a moment. Losing the
+/// last life ends the round.
+impl Plugin for HealthPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_event::<QueenDamaged>()
+            .init_resource::<GodMode>()
+            .add_systems(
+                FixedUpdate,
+                // Damage is sent while stepping the physics and applied in the same step
+                (damage_queen, blink_invulnerable)
+                    .chain()
+                    .after(PhysicsSet::Sync)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+const BLINK_INTERVAL: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageSource {
+    Wasp,
+    Hazard,
+}
+
+#[derive(Event)]
+pub struct QueenDamaged {
+    pub source: DamageSource,
+}
+
+#[derive(Component)]
+pub struct Lives(pub u32);
+
+/// While enabled the queen ignores all damage, used for testing
+#[derive(Resource, Default)]
+pub struct GodMode(pub bool);
+
+/// Damage is ignored until the timer finishes
+#[derive(Component)]
+pub struct Invulnerable {
+    timer: Timer,
+    blink: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(duration: f32) -> Self {
+        Invulnerable {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            blink: Timer::from_seconds(BLINK_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+fn damage_queen(
+    mut commands: Commands,
+    mut damage_events: EventReader<QueenDamaged>,
+    mut hit_events: EventWriter<QueenHit>,
+    mut next_state: ResMut<NextState<GameState>>,
+    god_mode: Res<GodMode>,
+    layout: Res<ArenaLayout>,
+    tuning: Res<Tuning>,
+    mut queen_query: Query<
+        (Entity, &mut Lives, &mut Position, &mut LinearVelocity),
+        (With<QueenBee>, Without<Invulnerable>),
+    >,
+) {
+    // Several hazards can hit in the same frame, but only one of them counts
+    let Some(damage) = damage_events.read().last() else {
+        return;
+    };
+    if god_mode.0 {
+        return;
+    }
+    let Ok((entity, mut lives, mut position, mut linear_velocity)) = queen_query.get_single_mut()
+    else {
+        return;
+    };
+
+    lives.0 = lives.0.saturating_sub(1);
+    hit_events.send(QueenHit {
+        position: position.0,
+        lives_left: lives.0,
+    });
+    info!("Queen hit by {:?}, {} lives left", damage.source, lives.0);
+    if lives.0 == 0 {
+        next_state.set(GameState::GameOver);
+        return;
+    }
+
+    position.0 = layout.queen_spawn;
+    linear_velocity.0 = Vector::ZERO;
+    commands
+        .entity(entity)
+        .insert(Invulnerable::new(tuning.invulnerable_time));
+}
+
+fn blink_invulnerable(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut invulnerable_query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut invulnerable_query {
+        if invulnerable.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+        } else if invulnerable.blink.tick(time.delta()).just_finished() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::arena::TestPlay;
+use crate::difficulty::{Difficulty, DifficultyPreset};
+use crate::events::RoundEnded;
+use crate::replay::{Replay, ReplayPlayback, ReplayRecording};
+use crate::scoreboard::end_round;
+use crate::time_attack::GameMode;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct HighScoresPlugin;
+
+/// This plugin records the result of every round together with the difficulty it was played on
+/// On native platforms the table is kept in a RON file next to the game, and every score in it
+/// keeps the [`Replay`] of its round. Watching a replay or a time attack round doesn't count.
+impl Plugin for HighScoresPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.insert_resource(HighScores::load()).add_systems(
+            OnEnter(GameState::GameOver),
+            record_high_score.after(end_round),
+        );
+    }
+}
+
+const MAX_HIGH_SCORES: usize = 10;
+#[cfg(not(target_arch = "wasm32"))]
+const HIGH_SCORES_PATH: &str = "high_scores.ron";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub points: f32,
+    pub bees: usize,
+    pub difficulty: DifficultyPreset,
+    /// Name of the replay file of the round
+    #[serde(default)]
+    pub replay: Option<String>,
+}
+
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    /// Index into `entries` of the score set by the last round, if it made the table
+    #[serde(skip)]
+    pub latest: Option<usize>,
+}
+
+impl HighScores {
+    /// Adds a score and returns its place in the table, or `None` if it didn't make the cut
+    pub fn insert(
+        &mut self,
+        high_score: HighScore,
+    ) -> Option<usize> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.points < high_score.points)
+            .unwrap_or(self.entries.len());
+        if index >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(index, high_score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(index)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Self {
+        std::fs::read_to_string(HIGH_SCORES_PATH)
+            .ok()
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load() -> Self {
+        HighScores::default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                std::fs::write(HIGH_SCORES_PATH, contents).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!("Failed to save high scores {error}");
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn save(&self) {}
+}
+
+pub fn record_high_score(
+    mut round_ended_events: EventReader<RoundEnded>,
+    difficulty: Res<Difficulty>,
+    recording: Res<ReplayRecording>,
+    playback: Option<Res<ReplayPlayback>>,
+    test_play: Option<Res<TestPlay>>,
+    game_mode: Res<GameMode>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    if playback.is_some() || test_play.is_some() || *game_mode != GameMode::Classic {
+        round_ended_events.clear();
+        high_scores.latest = None;
+        return;
+    }
+    for round in round_ended_events.read() {
+        // The last score drops out of a full table, together with its replay
+        let dropped = if high_scores.entries.len() >= MAX_HIGH_SCORES {
+            high_scores
+                .entries
+                .last()
+                .and_then(|entry| entry.replay.clone())
+        } else {
+            None
+        };
+        high_scores.latest = high_scores.insert(HighScore {
+            points: round.points,
+            bees: round.bees,
+            difficulty: difficulty.preset,
+            replay: None,
+        });
+        let Some(index) = high_scores.latest else {
+            continue;
+        };
+        if let Some(dropped) = dropped {
+            Replay::delete(&dropped);
+        }
+        let mut replay = recording.0.clone();
+        replay.points = round.points;
+        replay.bees = round.bees;
+        high_scores.entries[index].replay = replay.save();
+        high_scores.save();
+    }
+}
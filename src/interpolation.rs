@@ -0,0 +1,81 @@
+#![allow(clippy::unnecessary_cast)]
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_xpbd_2d::prelude::*;
+
+pub struct InterpolationPlugin;
+
+/// This plugin smooths the movement of [`Interpolated`] bodies between two fixed gameplay steps.
+/// The bodies themselves stay where the simulation put them, only their children are offset to
+/// the position between the last two steps that matches the time left over in the fixed clock.
+impl Plugin for InterpolationPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_systems(FixedUpdate, record_positions.after(PhysicsSet::Sync))
+            .add_systems(
+                PostUpdate,
+                offset_children.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Bodies that move further than this in a single step were teleported and are not smoothed
+const MAX_STEP_DISTANCE: f32 = 100.0;
+
+/// The position of a body after the last two fixed steps
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec2,
+    current: Vec2,
+}
+
+impl Interpolated {
+    pub fn new(position: Vec2) -> Self {
+        Interpolated {
+            previous: position,
+            current: position,
+        }
+    }
+
+    /// Where the body is drawn, `fixed_time` tells how far the clock got into the next step
+    pub fn position(
+        &self,
+        fixed_time: &Time<Fixed>,
+    ) -> Vec2 {
+        self.previous
+            .lerp(self.current, fixed_time.overstep_percentage())
+    }
+}
+
+fn record_positions(mut bodies: Query<(&Position, &mut Interpolated)>) {
+    for (position, mut interpolated) in &mut bodies {
+        let position = Vec2::new(position.x as f32, position.y as f32);
+        interpolated.previous = if position.distance(interpolated.current) > MAX_STEP_DISTANCE {
+            position
+        } else {
+            interpolated.current
+        };
+        interpolated.current = position;
+    }
+}
+
+fn offset_children(
+    fixed_time: Res<Time<Fixed>>,
+    bodies: Query<(&Interpolated, &Transform, &Children)>,
+    mut transforms: Query<&mut Transform, Without<Interpolated>>,
+) {
+    for (interpolated, body_transform, children) in &bodies {
+        let offset = (interpolated.position(&fixed_time) - body_transform.translation.truncate())
+            .extend(0.0);
+        // The children are placed in the body's frame, which turns with it
+        let local_offset = body_transform.rotation.inverse() * offset;
+        let mut iter = transforms.iter_many_mut(children);
+        while let Some(mut transform) = iter.fetch_next() {
+            transform.translation.x = local_offset.x;
+            transform.translation.y = local_offset.y;
+        }
+    }
+}
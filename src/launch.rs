@@ -0,0 +1,184 @@
+//! Command line options of the native binary
+//!
+//! queen_beevy --seed 42 --difficulty hard --mode time-attack --arena maze.arena.ron --skip-menu
+//!
+//! The options are parsed in `main` before the app is built. The window options are applied to the
+//! primary window there, everything else by the [`LaunchPlugin`].
+
+use crate::arena::{ArenaLayout, ArenaOverride};
+use crate::debug_overlay::DebugOverlay;
+use crate::difficulty::{Difficulty, DifficultyPreset};
+use crate::game_rng::GameRng;
+use crate::replay::{Replay, ReplayPlayback};
+use crate::time_attack::GameMode;
+use crate::tuning::Tuning;
+use crate::GameState;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: queen_beevy [OPTIONS]
+
+Options:
+  --window-size WIDTHxHEIGHT       Size of the window in logical pixels
+  --fullscreen                     Start in borderless fullscreen
+  --seed N                         Play every round with this seed
+  --difficulty easy|normal|hard|custom
+  --mode classic|time-attack       Game mode
+  --arena FILE                     Play in the arena layout saved in this file instead of the
+                                   one in the assets
+  --skip-menu                      Start a round as soon as the assets are loaded
+  --debug                          Show the debug overlay from the start
+  --replay FILE                    Watch a recorded replay, implies --skip-menu
+  --headless                       Play a single round without a window and print its score,
+                                   played by the bot unless a replay is given
+  -h, --help                       Print this message";
+
+#[derive(Resource, Clone, Default, Debug)]
+pub struct LaunchOptions {
+    pub window_size: Option<Vec2>,
+    pub fullscreen: bool,
+    pub seed: Option<u64>,
+    pub difficulty: Option<DifficultyPreset>,
+    pub mode: Option<GameMode>,
+    pub arena: Option<PathBuf>,
+    /// Cleared once the menu was skipped, so the menu shows up again after the first round
+    pub skip_menu: bool,
+    pub debug_overlay: bool,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    /// Only print the [`USAGE`]
+    pub help: bool,
+}
+
+impl LaunchOptions {
+    /// Parses the arguments without the program name, the error is a message for the user
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<LaunchOptions, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {arg}"));
+            match arg.as_str() {
+                "--window-size" => {
+                    let size = value()?;
+                    options.window_size =
+                        Some(parse_size(&size).ok_or(format!("Invalid --window-size {size}"))?);
+                }
+                "--fullscreen" => options.fullscreen = true,
+                "--seed" => {
+                    options.seed = Some(value()?.parse().map_err(|_| "Invalid --seed")?);
+                }
+                "--difficulty" => {
+                    let name = value()?;
+                    options.difficulty = Some(
+                        DifficultyPreset::from_name(&name)
+                            .ok_or(format!("Unknown difficulty {name}"))?,
+                    );
+                }
+                "--mode" => {
+                    let name = value()?;
+                    options.mode = Some(match name.to_ascii_lowercase().as_str() {
+                        "classic" => GameMode::Classic,
+                        "time-attack" => GameMode::TimeAttack,
+                        _ => return Err(format!("Unknown mode {name}")),
+                    });
+                }
+                "--arena" => options.arena = Some(PathBuf::from(value()?)),
+                "--skip-menu" => options.skip_menu = true,
+                "--debug" => options.debug_overlay = true,
+                "--replay" => {
+                    options.replay = Some(PathBuf::from(value()?));
+                    options.skip_menu = true;
+                }
+                "--headless" => options.headless = true,
+                "--help" | "-h" => {
+                    options.help = true;
+                    return Ok(options);
+                }
+                _ => return Err(format!("Unknown option {arg}\n{USAGE}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// `1280x720` as a size
+fn parse_size(size: &str) -> Option<Vec2> {
+    let (width, height) = size.split_once('x')?;
+    let size = Vec2::new(width.trim().parse().ok()?, height.trim().parse().ok()?);
+    (size.x > 0.0 && size.y > 0.0).then_some(size)
+}
+
+pub struct LaunchPlugin(pub LaunchOptions);
+
+/// This plugin applies the [`LaunchOptions`] to the game once it starts, it has to be added after
+/// the [`GamePlugin`](crate::GamePlugin) or [`GameplayPlugin`](crate::GameplayPlugin)
+impl Plugin for LaunchPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.insert_resource(self.0.clone())
+            .add_systems(Startup, apply_launch_options)
+            .add_systems(
+                OnEnter(GameState::Menu),
+                skip_menu.run_if(|options: Res<LaunchOptions>| options.skip_menu),
+            );
+    }
+}
+
+fn apply_launch_options(
+    mut commands: Commands,
+    options: Res<LaunchOptions>,
+    mut game_rng: ResMut<GameRng>,
+    mut difficulty: ResMut<Difficulty>,
+    mut game_mode: ResMut<GameMode>,
+    mut tuning: ResMut<Tuning>,
+    mut layout: ResMut<ArenaLayout>,
+    debug_overlay: Option<ResMut<DebugOverlay>>,
+) {
+    if let Some(seed) = options.seed {
+        game_rng.fixed_seed = Some(seed);
+    }
+    if let Some(preset) = options.difficulty {
+        difficulty.select(preset);
+    }
+    if let Some(mode) = options.mode {
+        *game_mode = mode;
+    }
+    if let Some(path) = &options.arena {
+        match ArenaLayout::load_file(path) {
+            Ok(arena) => {
+                *layout = arena;
+                commands.insert_resource(ArenaOverride);
+            }
+            Err(error) => error!("Failed to load arena {} {error}", path.display()),
+        }
+    }
+    if options.debug_overlay {
+        if let Some(mut debug_overlay) = debug_overlay {
+            debug_overlay.enabled = true;
+        }
+    }
+    if let Some(path) = &options.replay {
+        match Replay::load_file(path) {
+            Ok(replay) => {
+                commands.insert_resource(ReplayPlayback::new(
+                    replay,
+                    &mut difficulty,
+                    &mut game_mode,
+                    &mut tuning,
+                    &mut layout,
+                ));
+            }
+            Err(error) => error!("Failed to load replay {} {error}", path.display()),
+        }
+    }
+}
+
+fn skip_menu(
+    mut options: ResMut<LaunchOptions>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    options.skip_menu = false;
+    next_state.set(GameState::Playing);
+}
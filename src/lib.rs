@@ -1,31 +1,100 @@
 #![allow(clippy::type_complexity)]
 
 mod actions;
+mod animation;
+mod arena;
+#[cfg(feature = "dev")]
+mod arena_editor;
 mod audio;
 mod bee_spawner;
+mod bee_visuals;
 mod bees;
+pub mod bot;
+mod camera;
+#[cfg(feature = "dev")]
+mod console;
+mod debug_overlay;
+mod difficulty;
+mod events;
+mod flowers;
 mod fps_counter;
+mod game_over;
+mod game_rng;
+mod ghost;
+pub mod headless;
+mod health;
+mod high_scores;
+mod interpolation;
+pub mod launch;
 mod loading;
 mod menu;
+mod particles;
+mod pickups;
+mod replay;
+mod replay_browser;
+mod ron_asset;
 mod scoreboard;
+mod settings;
+mod time_attack;
+mod tuning;
+mod wasps;
 
 use crate::actions::ActionsPlugin;
+use crate::animation::AnimationPlugin;
+use crate::arena::ArenaPlugin;
+#[cfg(feature = "dev")]
+use crate::arena_editor::ArenaEditorPlugin;
 use crate::audio::InternalAudioPlugin;
+use crate::bee_visuals::BeeVisualsPlugin;
 use crate::bees::BeesPlugin;
+#[cfg(feature = "dev")]
+use crate::console::ConsolePlugin;
+use crate::debug_overlay::DebugOverlayPlugin;
+use crate::difficulty::DifficultyPlugin;
+use crate::events::GameplayEventsPlugin;
+use crate::flowers::FlowersPlugin;
+use crate::fps_counter::FPSCounterPlugin;
+use crate::game_over::GameOverPlugin;
+use crate::game_rng::GameRngPlugin;
+use crate::ghost::GhostPlugin;
+use crate::health::HealthPlugin;
+use crate::high_scores::HighScoresPlugin;
 use crate::loading::LoadingPlugin;
 use crate::menu::MenuPlugin;
-use crate::scoreboard::ScoreboardPlugin;
+use crate::particles::ParticlesPlugin;
+use crate::pickups::PickupsPlugin;
+use crate::replay::ReplayPlugin;
+use crate::replay_browser::ReplayBrowserPlugin;
+use crate::scoreboard::{ScorePlugin, ScoreboardPlugin};
+use crate::settings::SettingsPlugin;
+use crate::time_attack::TimeAttackPlugin;
+use crate::tuning::TuningPlugin;
+use crate::wasps::WaspsPlugin;
 
 use bevy::app::App;
-#[cfg(debug_assertions)]
-use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::ecs::schedule::ExecutorKind;
 use bevy::prelude::*;
 
+pub use crate::actions::Actions;
+pub use crate::arena::{ArenaLayout, ArenaRect};
+pub use crate::bees::{Production, QueenBee, WorkerBee};
+pub use crate::camera::{CameraController, CameraPlugin};
+pub use crate::difficulty::{Difficulty, DifficultyPreset};
+pub use crate::events::{FlowerCollected, QueenHit, RoundEnded, WorkerBeeDied, WorkerBeeSpawned};
+pub use crate::flowers::{Flower, FlowerKind};
+pub use crate::game_rng::GameRng;
+pub use crate::interpolation::InterpolationPlugin;
+pub use crate::loading::TextureAssets;
+pub use crate::replay::{InputRun, Replay, ReplayPlayback, ReplayRecording};
+pub use crate::scoreboard::Score;
+pub use crate::time_attack::{GameMode, PositionTrack, TimeAttack};
+pub use crate::tuning::Tuning;
+
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
 // Or https://github.com/bevyengine/bevy/blob/main/examples/ecs/state.rs
 #[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
-enum GameState {
+pub enum GameState {
     // During the loading State the LoadingPlugin will load our assets
     #[default]
     Loading,
@@ -33,8 +102,21 @@ enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // The queen ran out of lives, the results are shown until the player returns to the menu
+    GameOver,
+    // The recorded replays are listed to pick one to watch
+    Replays,
+    // Designers place the walls, hazards and spawn points of the arena, only with the `dev` feature
+    Editor,
 }
 
+/// Rate of the fixed steps that gameplay and physics advance in, independent of the frame rate
+pub const FIXED_TIMESTEP_HZ: f64 = 60.0;
+
+/// Marks entities that belong to a single round, they are despawned once the round is over
+#[derive(Component)]
+struct RoundEntity;
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
@@ -42,19 +124,70 @@ impl Plugin for GamePlugin {
         &self,
         app: &mut App,
     ) {
-        app.add_state::<GameState>().add_plugins((
-            LoadingPlugin,
-            MenuPlugin,
-            ActionsPlugin,
-            InternalAudioPlugin,
-            BeesPlugin,
-            ScoreboardPlugin,
-            // FPSCounterPlugin,
-        ));
-
-        #[cfg(debug_assertions)]
-        {
-            app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
-        }
+        app.add_plugins(GameplayPlugin)
+            .add_plugins((
+                SettingsPlugin,
+                LoadingPlugin,
+                CameraPlugin,
+                MenuPlugin,
+                ActionsPlugin,
+                InternalAudioPlugin,
+                ScoreboardPlugin,
+                GameOverPlugin,
+                HighScoresPlugin,
+                ReplayBrowserPlugin,
+                GhostPlugin,
+                TuningPlugin,
+                ArenaPlugin,
+            ))
+            .add_plugins((
+                InterpolationPlugin,
+                BeeVisualsPlugin,
+                AnimationPlugin,
+                ParticlesPlugin,
+                DebugOverlayPlugin,
+                FPSCounterPlugin,
+            ));
+
+        #[cfg(feature = "dev")]
+        app.add_plugins((ConsolePlugin, ArenaEditorPlugin));
+    }
+}
+
+/// The gameplay core: the bees, flowers, pickups, wasps, physics and scoring of a round
+/// It doesn't need a window, rendering, input devices or audio, so it also runs headless, see [`headless`]
+///
+/// Gameplay runs in [`FixedUpdate`] on a single thread and draws from the seeded [`GameRng`], so the
+/// same seed and input produce the same round at any frame rate.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_state::<GameState>()
+            .init_resource::<Actions>()
+            .init_resource::<Tuning>()
+            .register_type::<Tuning>()
+            .init_resource::<ArenaLayout>()
+            .register_type::<ArenaLayout>()
+            .insert_resource(Time::<Fixed>::from_hz(FIXED_TIMESTEP_HZ))
+            .edit_schedule(FixedUpdate, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            })
+            .add_plugins((
+                GameplayEventsPlugin,
+                GameRngPlugin,
+                ReplayPlugin,
+                DifficultyPlugin,
+                BeesPlugin,
+                FlowersPlugin,
+                PickupsPlugin,
+                WaspsPlugin,
+                HealthPlugin,
+                ScorePlugin,
+                TimeAttackPlugin,
+            ));
     }
 }
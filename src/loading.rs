@@ -1,3 +1,6 @@
+use crate::animation::AnimationLibrary;
+use crate::arena::ArenaLayout;
+use crate::tuning::Tuning;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
@@ -17,7 +20,10 @@ impl Plugin for LoadingPlugin {
             LoadingState::new(GameState::Loading).continue_to_state(GameState::Menu),
         )
         .add_collection_to_loading_state::<_, AudioAssets>(GameState::Loading)
-        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading);
+        .add_collection_to_loading_state::<_, TextureAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, AnimationAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, TuningAssets>(GameState::Loading)
+        .add_collection_to_loading_state::<_, ArenaAssets>(GameState::Loading);
     }
 }
 
@@ -28,8 +34,20 @@ impl Plugin for LoadingPlugin {
 pub struct AudioAssets {
     #[asset(path = "audio/Rimsky-Korsakov_-_flight_of_the_bumblebee.oga")]
     pub background_music: Handle<AudioSource>,
+    #[asset(path = "audio/menu-music.wav")]
+    pub menu_music: Handle<AudioSource>,
+    #[asset(path = "audio/results-music.wav")]
+    pub results_music: Handle<AudioSource>,
+    #[asset(path = "audio/swarm-layer.wav")]
+    pub swarm_layer: Handle<AudioSource>,
     #[asset(path = "audio/flower-get.ogg")]
     pub flower: Handle<AudioSource>,
+    #[asset(path = "audio/flower-stinger.wav")]
+    pub flower_stinger: Handle<AudioSource>,
+    #[asset(path = "audio/bee-death.ogg")]
+    pub bee_death: Handle<AudioSource>,
+    #[asset(path = "audio/ui-click.wav")]
+    pub ui_click: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -38,10 +56,53 @@ pub struct TextureAssets {
     pub bevy: Handle<Image>,
     #[asset(path = "textures/github.png")]
     pub github: Handle<Image>,
-    #[asset(path = "textures/queen-bee.png")]
-    pub queen: Handle<Image>,
-    #[asset(path = "textures/bee.png")]
-    pub bee: Handle<Image>,
-    #[asset(path = "textures/flower.png")]
-    pub flower: Handle<Image>,
+    #[asset(texture_atlas(tile_size_x = 64., tile_size_y = 64., columns = 4, rows = 4))]
+    #[asset(path = "textures/queen-bee-sheet.png")]
+    pub queen: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 16., columns = 4, rows = 4))]
+    #[asset(path = "textures/bee-sheet.png")]
+    pub bee: Handle<TextureAtlas>,
+    #[asset(texture_atlas(tile_size_x = 64., tile_size_y = 64., columns = 4, rows = 1))]
+    #[asset(path = "textures/flower-sheet.png")]
+    pub flower: Handle<TextureAtlas>,
+    #[asset(path = "textures/honeycomb.png")]
+    pub honeycomb: Handle<Image>,
+    #[asset(path = "textures/wasp.png")]
+    pub wasp: Handle<Image>,
+    #[asset(path = "textures/spikes.png")]
+    pub spikes: Handle<Image>,
+}
+
+impl TextureAssets {
+    /// Placeholder handles for running the gameplay without loading any assets
+    pub fn stub() -> Self {
+        TextureAssets {
+            bevy: Handle::default(),
+            github: Handle::default(),
+            queen: Handle::default(),
+            bee: Handle::default(),
+            flower: Handle::default(),
+            honeycomb: Handle::default(),
+            wasp: Handle::default(),
+            spikes: Handle::default(),
+        }
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct AnimationAssets {
+    #[asset(path = "animations.anim.ron")]
+    pub library: Handle<AnimationLibrary>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct TuningAssets {
+    #[asset(path = "tuning.ron")]
+    pub tuning: Handle<Tuning>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ArenaAssets {
+    #[asset(path = "arena.arena.ron")]
+    pub layout: Handle<ArenaLayout>,
 }
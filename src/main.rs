@@ -3,31 +3,97 @@
 
 use bevy::asset::AssetMetaCheck;
 use bevy::prelude::*;
+use bevy::window::{WindowMode, WindowResolution};
 use bevy::DefaultPlugins;
-use queen_beevy::GamePlugin;
+use queen_beevy::bot::BotPlugin;
+use queen_beevy::headless::{headless_app, start_round};
+use queen_beevy::launch::{LaunchOptions, LaunchPlugin, USAGE};
+use queen_beevy::{GamePlugin, GameState, ReplayPlayback, Score, FIXED_TIMESTEP_HZ};
+
+/// Headless rounds the bot survives this long are stopped
+const MAX_HEADLESS_SECONDS: f64 = 30.0 * 60.0;
 
 fn main() {
+    let options = match LaunchOptions::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{USAGE}");
+        return;
+    }
+    if options.headless {
+        run_headless(options);
+        return;
+    }
+
+    let mut window = Window {
+        title: "Queen Beevy".to_string(), // ToDo
+        // Bind to canvas included in `index.html`
+        canvas: Some("#bevy".to_owned()),
+        // The canvas size is constrained in index.html and build/web/styles.css
+        fit_canvas_to_parent: true,
+        // Tells wasm not to override default event handling, like F5 and Ctrl+R
+        prevent_default_event_handling: false,
+        ..default()
+    };
+    if let Some(size) = options.window_size {
+        window.resolution = WindowResolution::new(size.x, size.y);
+    }
+    if options.fullscreen {
+        window.mode = WindowMode::BorderlessFullscreen;
+    }
+
     App::new()
         .insert_resource(AssetMetaCheck::Never)
         .insert_resource(Msaa::Off)
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Queen Beevy".to_string(), // ToDo
-                // Bind to canvas included in `index.html`
-                canvas: Some("#bevy".to_owned()),
-                // The canvas size is constrained in index.html and build/web/styles.css
-                fit_canvas_to_parent: true,
-                // Tells wasm not to override default event handling, like F5 and Ctrl+R
-                prevent_default_event_handling: false,
-                // mode: WindowMode::BorderlessFullscreen,
-                // resolution: WindowResolution::new(640., 360.)
-                //             .with_scale_factor_override(3.0),
-                ..default()
-            }),
+            primary_window: Some(window),
             ..default()
         }))
         .add_plugins(GamePlugin)
+        .add_plugins(LaunchPlugin(options))
         // .add_systems(Startup, set_window_icon)
         .run();
 }
+
+/// Plays a single round without a window, the replay if one was given and the bot otherwise
+fn run_headless(options: LaunchOptions) {
+    let mut app = headless_app();
+    if options.replay.is_none() {
+        app.add_plugins(BotPlugin);
+    }
+    let replay = options.replay.clone();
+    app.add_plugins(LaunchPlugin(options));
+    start_round(&mut app);
+    if let Some(path) = replay {
+        if !app.world.contains_resource::<ReplayPlayback>() {
+            eprintln!("Failed to load replay {}", path.display());
+            std::process::exit(1);
+        }
+    }
+
+    // Every update of the headless app is one fixed step
+    let max_steps = app.world.get_resource::<ReplayPlayback>().map_or(
+        (MAX_HEADLESS_SECONDS * FIXED_TIMESTEP_HZ) as usize,
+        |playback| playback.replay.steps(),
+    );
+    let mut steps = 0;
+    while steps < max_steps && *app.world.resource::<State<GameState>>().get() == GameState::Playing
+    {
+        app.update();
+        steps += 1;
+    }
+
+    let score = app.world.resource::<Score>();
+    println!(
+        "{:.1} seconds, {} points, {} bees",
+        steps as f64 / FIXED_TIMESTEP_HZ,
+        score.points,
+        score.bees
+    );
+}
@@ -1,5 +1,8 @@
 use crate::actions::{Actions, InputDevice};
+use crate::audio::{PlaySound, SoundEffect};
+use crate::difficulty::Difficulty;
 use crate::loading::TextureAssets;
+use crate::time_attack::GameMode;
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -13,15 +16,26 @@ impl Plugin for MenuPlugin {
         app: &mut App,
     ) {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
-            .add_systems(Update, click_play_button.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                (
+                    click_play_button.run_if(
+                        in_state(GameState::Menu)
+                            .or_else(in_state(GameState::GameOver))
+                            .or_else(in_state(GameState::Replays)),
+                    ),
+                    (update_difficulty_text, update_game_mode_text)
+                        .run_if(in_state(GameState::Menu)),
+                ),
+            )
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
     }
 }
 
 #[derive(Component)]
-struct ButtonColors {
-    normal: Color,
-    hovered: Color,
+pub(crate) struct ButtonColors {
+    pub(crate) normal: Color,
+    pub(crate) hovered: Color,
 }
 
 impl Default for ButtonColors {
@@ -39,9 +53,10 @@ struct Menu;
 fn setup_menu(
     mut commands: Commands,
     textures: Res<TextureAssets>,
+    difficulty: Res<Difficulty>,
+    game_mode: Res<GameMode>,
 ) {
     info!("menu");
-    commands.spawn(Camera2dBundle::default());
     commands
         .spawn((
             NodeBundle {
@@ -190,6 +205,126 @@ fn setup_menu(
                     ..default()
                 }),
             );
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(400.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    CycleDifficulty,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("Difficulty: {}", difficulty.preset.name()),
+                            TextStyle {
+                                font_size: 32.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        DifficultyText,
+                    ));
+                });
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(400.0),
+                            height: Val::Px(50.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    CycleGameMode,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("Mode: {}", game_mode.name()),
+                            TextStyle {
+                                font_size: 32.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ),
+                        GameModeText,
+                    ));
+                });
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(400.0),
+                            height: Val::Px(50.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    ChangeState(GameState::Replays),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Replays",
+                        TextStyle {
+                            font_size: 32.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+            #[cfg(feature = "dev")]
+            {
+                let button_colors = ButtonColors::default();
+                children
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(400.0),
+                                height: Val::Px(50.0),
+                                margin: UiRect::top(Val::Px(10.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: button_colors.normal.into(),
+                            ..Default::default()
+                        },
+                        button_colors,
+                        ChangeState(GameState::Editor),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "Arena Editor",
+                            TextStyle {
+                                font_size: 32.0,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
         });
     commands
         .spawn((
@@ -288,7 +423,7 @@ fn setup_menu(
 }
 
 #[derive(Component)]
-struct ChangeState(GameState);
+pub(crate) struct ChangeState(pub(crate) GameState);
 
 #[derive(Component)]
 struct ChangeInput(InputDevice);
@@ -296,6 +431,18 @@ struct ChangeInput(InputDevice);
 #[derive(Component)]
 struct OpenLink(&'static str);
 
+#[derive(Component)]
+struct CycleDifficulty;
+
+#[derive(Component)]
+struct DifficultyText;
+
+#[derive(Component)]
+struct CycleGameMode;
+
+#[derive(Component)]
+struct GameModeText;
+
 fn click_play_button(
     mut next_state: ResMut<NextState<GameState>>,
     mut interaction_query: Query<
@@ -306,16 +453,33 @@ fn click_play_button(
             Option<&ChangeState>,
             Option<&OpenLink>,
             Option<&ChangeInput>,
+            Has<CycleDifficulty>,
+            Has<CycleGameMode>,
         ),
         (Changed<Interaction>, With<Button>),
     >,
     mut actions: ResMut<Actions>,
+    mut difficulty: ResMut<Difficulty>,
+    mut game_mode: ResMut<GameMode>,
+    mut sound_events: EventWriter<PlaySound>,
 ) {
-    for (interaction, mut color, button_colors, change_state, open_link, change_input) in
-        &mut interaction_query
+    for (
+        interaction,
+        mut color,
+        button_colors,
+        change_state,
+        open_link,
+        change_input,
+        cycle_difficulty,
+        cycle_game_mode,
+    ) in &mut interaction_query
     {
         match *interaction {
             Interaction::Pressed => {
+                sound_events.send(PlaySound {
+                    sound: SoundEffect::UiClick,
+                    position: None,
+                });
                 if let Some(state) = change_state {
                     if let Some(device) = change_input {
                         match device.0 {
@@ -328,6 +492,11 @@ fn click_play_button(
                     if let Err(error) = webbrowser::open(link.0) {
                         warn!("Failed to open link {error:?}");
                     }
+                } else if cycle_difficulty {
+                    let next = difficulty.next_preset();
+                    difficulty.select(next);
+                } else if cycle_game_mode {
+                    *game_mode = game_mode.next();
                 }
             }
             Interaction::Hovered => {
@@ -340,6 +509,30 @@ fn click_play_button(
     }
 }
 
+fn update_difficulty_text(
+    difficulty: Res<Difficulty>,
+    mut text_query: Query<&mut Text, With<DifficultyText>>,
+) {
+    if !difficulty.is_changed() {
+        return;
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Difficulty: {}", difficulty.preset.name());
+    }
+}
+
+fn update_game_mode_text(
+    game_mode: Res<GameMode>,
+    mut text_query: Query<&mut Text, With<GameModeText>>,
+) {
+    if !game_mode.is_changed() {
+        return;
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Mode: {}", game_mode.name());
+    }
+}
+
 fn cleanup_menu(
     mut commands: Commands,
     menu: Query<Entity, With<Menu>>,
@@ -0,0 +1,232 @@
+use crate::bees::QueenBee;
+use crate::events::{FlowerCollected, WorkerBeeDied};
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use rand::prelude::*;
+
+pub struct ParticlesPlugin;
+
+/// This plugin is a small CPU particle system built on plain sprites. Gameplay systems send
+/// [`SpawnParticles`] events for one-off bursts, and [`ParticleEmitter`] components emit
+/// continuously. The number of live particles is capped to keep WebGL2 builds cheap.
+impl Plugin for ParticlesPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_event::<SpawnParticles>().add_systems(
+            Update,
+            (
+                attach_pollen_trail,
+                emit_gameplay_particles,
+                emit_particles,
+                spawn_particles,
+                update_particles,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::GameOver))),
+        );
+    }
+}
+
+const MAX_PARTICLES: usize = 600;
+/// The queen only leaves a pollen trail when moving faster than this
+const POLLEN_TRAIL_MIN_SPEED: f32 = 60.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticlePreset {
+    FlowerBurst,
+    PollenTrail,
+    DeathPuff,
+}
+
+struct PresetSettings {
+    count: usize,
+    speed: (f32, f32),
+    lifetime: f32,
+    size: (f32, f32),
+    end_color: Color,
+    drag: f32,
+}
+
+impl ParticlePreset {
+    fn settings(&self) -> PresetSettings {
+        match self {
+            ParticlePreset::FlowerBurst => PresetSettings {
+                count: 24,
+                speed: (80.0, 220.0),
+                lifetime: 0.6,
+                size: (7.0, 2.0),
+                end_color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                drag: 3.0,
+            },
+            ParticlePreset::PollenTrail => PresetSettings {
+                count: 1,
+                speed: (5.0, 25.0),
+                lifetime: 0.5,
+                size: (4.0, 1.0),
+                end_color: Color::rgba(1.0, 0.9, 0.3, 0.0),
+                drag: 1.0,
+            },
+            ParticlePreset::DeathPuff => PresetSettings {
+                count: 8,
+                speed: (30.0, 90.0),
+                lifetime: 0.4,
+                size: (5.0, 9.0),
+                end_color: Color::rgba(0.3, 0.3, 0.3, 0.0),
+                drag: 4.0,
+            },
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct SpawnParticles {
+    pub preset: ParticlePreset,
+    pub position: Vec2,
+    pub color: Color,
+}
+
+/// Spawns a particle of the given preset at the entity's position every `timer` interval
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub preset: ParticlePreset,
+    pub color: Color,
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+    start_color: Color,
+    end_color: Color,
+    size: (f32, f32),
+    drag: f32,
+}
+
+fn attach_pollen_trail(
+    mut commands: Commands,
+    queens: Query<Entity, Added<QueenBee>>,
+) {
+    for queen in &queens {
+        commands.entity(queen).insert(ParticleEmitter {
+            preset: ParticlePreset::PollenTrail,
+            color: Color::rgb(1.0, 0.9, 0.3),
+            timer: Timer::from_seconds(0.03, TimerMode::Repeating),
+        });
+    }
+}
+
+fn emit_gameplay_particles(
+    mut collected_events: EventReader<FlowerCollected>,
+    mut died_events: EventReader<WorkerBeeDied>,
+    mut particle_events: EventWriter<SpawnParticles>,
+) {
+    for collected in collected_events.read() {
+        particle_events.send(SpawnParticles {
+            preset: ParticlePreset::FlowerBurst,
+            position: collected.position,
+            color: collected.flower.color(),
+        });
+    }
+    for died in died_events.read() {
+        particle_events.send(SpawnParticles {
+            preset: ParticlePreset::DeathPuff,
+            position: died.position,
+            color: Color::rgb(0.9, 0.9, 0.6),
+        });
+    }
+}
+
+fn emit_particles(
+    time: Res<Time>,
+    mut particle_events: EventWriter<SpawnParticles>,
+    mut emitters: Query<(&mut ParticleEmitter, &Transform, Option<&LinearVelocity>)>,
+) {
+    for (mut emitter, transform, linear_velocity) in &mut emitters {
+        if !emitter.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let moving =
+            linear_velocity.map_or(true, |velocity| velocity.length() > POLLEN_TRAIL_MIN_SPEED);
+        if emitter.preset == ParticlePreset::PollenTrail && !moving {
+            continue;
+        }
+        particle_events.send(SpawnParticles {
+            preset: emitter.preset,
+            position: transform.translation.truncate(),
+            color: emitter.color,
+        });
+    }
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    mut particle_events: EventReader<SpawnParticles>,
+    particles: Query<(), With<Particle>>,
+) {
+    let mut budget = MAX_PARTICLES.saturating_sub(particles.iter().count());
+    let mut rng = thread_rng();
+
+    for event in particle_events.read() {
+        let settings = event.preset.settings();
+        let count = settings.count.min(budget);
+        budget -= count;
+        for _ in 0..count {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let speed = rng.gen_range(settings.speed.0..settings.speed.1);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: event.color,
+                        custom_size: Some(Vec2::splat(settings.size.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(event.position.extend(2.)),
+                    ..default()
+                },
+                Particle {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    lifetime: Timer::from_seconds(settings.lifetime, TimerMode::Once),
+                    start_color: event.color,
+                    end_color: settings.end_color,
+                    size: settings.size,
+                    drag: settings.drag,
+                },
+                RoundEntity,
+            ));
+        }
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta_time = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in &mut particles {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let drag = (1.0 - particle.drag * delta_time).max(0.0);
+        particle.velocity *= drag;
+        transform.translation += particle.velocity.extend(0.) * delta_time;
+
+        let t = particle.lifetime.percent();
+        let start = particle.start_color.as_rgba_f32();
+        let end = particle.end_color.as_rgba_f32();
+        sprite.color = Color::rgba(
+            start[0] + (end[0] - start[0]) * t,
+            start[1] + (end[1] - start[1]) * t,
+            start[2] + (end[2] - start[2]) * t,
+            start[3] + (end[3] - start[3]) * t,
+        );
+        sprite.custom_size = Some(Vec2::splat(
+            particle.size.0 + (particle.size.1 - particle.size.0) * t,
+        ));
+    }
+}
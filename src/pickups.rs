@@ -0,0 +1,402 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::bee_visuals::BeeVisual;
+use crate::bees::{queen_collision_layers, Layer, QueenBee};
+use crate::game_rng::GameRng;
+use crate::loading::TextureAssets;
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy_xpbd_2d::{math::*, prelude::*};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct PickupsPlugin;
+
+/// This plugin periodically drops power-up pickups into the arena. Collecting one starts a
+/// [`TimedEffect`] that other systems can check with [`effect_active`]. Active effects are shown
+/// as icons with their remaining duration at the bottom of the screen.
+impl Plugin for PickupsPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<PickupSpawnTimer>()
+            .add_systems(OnEnter(GameState::Playing), setup)
+            .add_systems(
+                FixedUpdate,
+                (spawn_pickups, tick_effects, apply_ghost_effect)
+                    .before(PhysicsSet::Prepare)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                update_effect_icons.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostProcessCollisions,
+                pickup_collision.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// honeycomb.png is 64 pixels wide
+const PICKUP_SPRITE_SIZE: f32 = 64.0;
+
+/// Half the size of the area that pickups appear in
+pub fn pickup_area(tuning: &Tuning) -> Vec2 {
+    tuning.arena_size / 2.0 - tuning.pickups.margin
+}
+
+/// How often pickups appear and how long their effects last, part of the [`Tuning`]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct PickupTuning {
+    /// Seconds between two pickups, a new one only appears once the last one was collected
+    pub spawn_interval: f32,
+    /// Space kept free between the pickups and the walls
+    pub margin: Vec2,
+    /// Collider radius of a pickup
+    pub radius: f32,
+    /// Factor on the queen's acceleration while the speed boost is active
+    pub speed_boost_scaling: f32,
+    /// Seconds every effect lasts
+    pub speed_boost_duration: f32,
+    pub freeze_duration: f32,
+    pub ghost_duration: f32,
+    pub production_pause_duration: f32,
+}
+
+impl Default for PickupTuning {
+    fn default() -> Self {
+        PickupTuning {
+            spawn_interval: 10.0,
+            margin: Vec2::new(50.0, 80.0),
+            radius: 16.0,
+            speed_boost_scaling: 1.8,
+            speed_boost_duration: 6.0,
+            freeze_duration: 4.0,
+            ghost_duration: 5.0,
+            production_pause_duration: 8.0,
+        }
+    }
+}
+
+impl PickupTuning {
+    pub fn duration(
+        &self,
+        kind: EffectKind,
+    ) -> f32 {
+        match kind {
+            EffectKind::SpeedBoost => self.speed_boost_duration,
+            EffectKind::Freeze => self.freeze_duration,
+            EffectKind::Ghost => self.ghost_duration,
+            EffectKind::ProductionPause => self.production_pause_duration,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EffectKind {
+    /// The queen accelerates faster
+    SpeedBoost,
+    /// Worker bees stop in place and ignore the right stick
+    Freeze,
+    /// The queen passes through worker bees
+    Ghost,
+    /// The queen stops producing worker bees
+    ProductionPause,
+}
+
+impl EffectKind {
+    pub const ALL: [EffectKind; 4] = [
+        EffectKind::SpeedBoost,
+        EffectKind::Freeze,
+        EffectKind::Ghost,
+        EffectKind::ProductionPause,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            EffectKind::SpeedBoost => Color::rgb(1.0, 0.5, 0.1),
+            EffectKind::Freeze => Color::rgb(0.5, 0.9, 1.0),
+            EffectKind::Ghost => Color::rgba(0.9, 0.9, 1.0, 0.6),
+            EffectKind::ProductionPause => Color::rgb(0.4, 0.9, 0.3),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EffectKind::SpeedBoost => "Speed",
+            EffectKind::Freeze => "Freeze",
+            EffectKind::Ghost => "Ghost",
+            EffectKind::ProductionPause => "Pause",
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: EffectKind,
+}
+
+/// A running effect, despawned once its timer finishes
+#[derive(Component)]
+pub struct TimedEffect {
+    pub kind: EffectKind,
+    pub timer: Timer,
+}
+
+impl TimedEffect {
+    pub fn new(
+        kind: EffectKind,
+        duration: f32,
+    ) -> Self {
+        TimedEffect {
+            kind,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+/// Marks the [`TimedEffect`] of the ghost pickup
+#[derive(Component)]
+struct GhostEffect;
+
+pub fn effect_active(
+    effects: &Query<&TimedEffect>,
+    kind: EffectKind,
+) -> bool {
+    effects.iter().any(|effect| effect.kind == kind)
+}
+
+#[derive(Resource, Default)]
+struct PickupSpawnTimer(Timer);
+
+#[derive(Component)]
+struct EffectHud;
+
+#[derive(Component)]
+struct EffectIcon {
+    effect: Entity,
+}
+
+#[derive(Component)]
+struct EffectTimerText;
+
+fn setup(
+    mut commands: Commands,
+    tuning: Res<Tuning>,
+    mut spawn_timer: ResMut<PickupSpawnTimer>,
+) {
+    spawn_timer.0 = Timer::from_seconds(tuning.pickups.spawn_interval, TimerMode::Repeating);
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        },
+        EffectHud,
+        RoundEntity,
+    ));
+}
+
+fn spawn_pickups(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    tuning: Res<Tuning>,
+    time: Res<Time>,
+    mut game_rng: ResMut<GameRng>,
+    mut spawn_timer: ResMut<PickupSpawnTimer>,
+    pickups: Query<(), With<Pickup>>,
+) {
+    if !spawn_timer.0.tick(time.delta()).just_finished() || !pickups.is_empty() {
+        return;
+    }
+    let rng = game_rng.rng();
+    let kind = *EffectKind::ALL.choose(rng).unwrap();
+    let area = pickup_area(&tuning);
+    let position = Vec2::new(
+        rng.gen_range(-area.x..area.x),
+        rng.gen_range(-area.y..area.y),
+    );
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: kind.color(),
+                ..default()
+            },
+            texture: textures.honeycomb.clone(),
+            transform: Transform::from_translation(position.extend(1.)).with_scale(Vec3::splat(
+                tuning.pickups.radius * 2.0 / PICKUP_SPRITE_SIZE,
+            )),
+            ..default()
+        },
+        RigidBody::Static,
+        Collider::ball(tuning.pickups.radius as Scalar),
+        Sensor,
+        CollisionLayers::new([Layer::Pickup], [Layer::Queen]),
+        Pickup { kind },
+        RoundEntity,
+    ));
+}
+
+fn pickup_collision(
+    mut commands: Commands,
+    tuning: Res<Tuning>,
+    mut collision_event_reader: EventReader<CollisionStarted>,
+    queen_query: Query<(), With<QueenBee>>,
+    pickup_query: Query<&Pickup>,
+    mut effects: Query<&mut TimedEffect>,
+) {
+    for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
+        let pickup_entity = if queen_query.contains(*entity1) {
+            *entity2
+        } else if queen_query.contains(*entity2) {
+            *entity1
+        } else {
+            continue;
+        };
+        let Ok(pickup) = pickup_query.get(pickup_entity) else {
+            continue;
+        };
+        commands.entity(pickup_entity).despawn();
+
+        // Picking up an effect that is already running only refreshes its duration
+        if let Some(mut effect) = effects.iter_mut().find(|effect| effect.kind == pickup.kind) {
+            effect.timer.reset();
+        } else {
+            let mut effect = commands.spawn((
+                TimedEffect::new(pickup.kind, tuning.pickups.duration(pickup.kind)),
+                RoundEntity,
+            ));
+            if pickup.kind == EffectKind::Ghost {
+                effect.insert(GhostEffect);
+            }
+        }
+    }
+}
+
+fn tick_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut effects: Query<(Entity, &mut TimedEffect)>,
+) {
+    for (entity, mut effect) in &mut effects {
+        if effect.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Runs when the ghost effect starts or ends rather than every step, so the queen's collision
+/// layers and sprite color are free for other systems to change while it lasts
+fn apply_ghost_effect(
+    started: Query<(), Added<GhostEffect>>,
+    mut ended: RemovedComponents<GhostEffect>,
+    mut queen_query: Query<(&mut CollisionLayers, &Children), With<QueenBee>>,
+    mut sprites: Query<&mut TextureAtlasSprite, With<BeeVisual>>,
+) {
+    let ended = ended.read().count() > 0;
+    let ghost = if !started.is_empty() {
+        true
+    } else if ended {
+        false
+    } else {
+        return;
+    };
+    for (mut layers, children) in &mut queen_query {
+        *layers = queen_collision_layers(ghost);
+        let mut iter = sprites.iter_many_mut(children);
+        while let Some(mut sprite) = iter.fetch_next() {
+            sprite.color.set_a(if ghost { 0.5 } else { 1.0 });
+        }
+    }
+}
+
+fn update_effect_icons(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    effects: Query<(Entity, &TimedEffect)>,
+    hud_query: Query<Entity, With<EffectHud>>,
+    icon_query: Query<(Entity, &EffectIcon, &Children)>,
+    mut text_query: Query<&mut Text, With<EffectTimerText>>,
+) {
+    let Ok(hud) = hud_query.get_single() else {
+        return;
+    };
+
+    for (icon_entity, icon, children) in &icon_query {
+        let Ok((_, effect)) = effects.get(icon.effect) else {
+            commands.entity(icon_entity).despawn_recursive();
+            continue;
+        };
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = format!("{:.1}", effect.timer.remaining_secs());
+            }
+        }
+    }
+
+    for (effect_entity, effect) in &effects {
+        if icon_query
+            .iter()
+            .any(|(_, icon, _)| icon.effect == effect_entity)
+        {
+            continue;
+        }
+        commands.entity(hud).with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    EffectIcon {
+                        effect: effect_entity,
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn(ImageBundle {
+                        image: textures.honeycomb.clone().into(),
+                        background_color: effect.kind.color().into(),
+                        style: Style {
+                            width: Val::Px(32.),
+                            height: Val::Px(32.),
+                            ..default()
+                        },
+                        ..default()
+                    });
+                    parent.spawn(TextBundle::from_section(
+                        effect.kind.label(),
+                        TextStyle {
+                            font: default(),
+                            font_size: 14.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("{:.1}", effect.timer.remaining_secs()),
+                            TextStyle {
+                                font: default(),
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        EffectTimerText,
+                    ));
+                });
+        });
+    }
+}
@@ -0,0 +1,308 @@
+use crate::actions::{Actions, InputSet};
+use crate::arena::ArenaLayout;
+use crate::difficulty::{Difficulty, DifficultyPreset, DifficultySettings};
+use crate::game_rng::{reseed, GameRng};
+use crate::time_attack::GameMode;
+use crate::tuning::Tuning;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct ReplayPlugin;
+
+/// This plugin records the [`Actions`] of every fixed step of a round together with its seed and
+/// difficulty. While a [`ReplayPlayback`] exists, the recorded input is fed back into [`Actions`]
+/// instead, and the same gameplay systems play the round out exactly as it happened.
+impl Plugin for ReplayPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<ReplayRecording>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    start_recording.run_if(not(resource_exists::<ReplayPlayback>())),
+                    start_playback.run_if(resource_exists::<ReplayPlayback>()),
+                )
+                    .after(reseed),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Recorded once every input writer, like the bot, is done with the step
+                    record_input
+                        .after(InputSet)
+                        .run_if(not(resource_exists::<ReplayPlayback>())),
+                    play_back_input
+                        .in_set(InputSet)
+                        .run_if(resource_exists::<ReplayPlayback>()),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                stop_playback.run_if(resource_exists::<ReplayPlayback>()),
+            );
+    }
+}
+
+/// Replays only play back the same way on the version of the game that recorded them
+const REPLAY_VERSION: u32 = 4;
+#[cfg(not(target_arch = "wasm32"))]
+const REPLAY_DIRECTORY: &str = "replays";
+
+/// Consecutive fixed steps with the same input
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InputRun {
+    pub steps: u32,
+    pub movement: Vec2,
+    pub bee_movement: Vec2,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub difficulty: DifficultyPreset,
+    pub settings: DifficultySettings,
+    pub tuning: Tuning,
+    pub arena: ArenaLayout,
+    /// Final score of the round, filled in when it ends
+    pub points: f32,
+    pub bees: usize,
+    pub input: Vec<InputRun>,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            seed: 0,
+            difficulty: DifficultyPreset::default(),
+            settings: DifficultySettings::default(),
+            tuning: Tuning::default(),
+            arena: ArenaLayout::default(),
+            points: 0.0,
+            bees: 0,
+            input: Vec::new(),
+        }
+    }
+}
+
+impl Replay {
+    /// Number of fixed steps in the replay
+    pub fn steps(&self) -> usize {
+        self.input.iter().map(|run| run.steps as usize).sum()
+    }
+
+    fn push(
+        &mut self,
+        movement: Vec2,
+        bee_movement: Vec2,
+    ) {
+        if let Some(last) = self.input.last_mut() {
+            if last.movement == movement && last.bee_movement == bee_movement {
+                last.steps += 1;
+                return;
+            }
+        }
+        self.input.push(InputRun {
+            steps: 1,
+            movement,
+            bee_movement,
+        });
+    }
+
+    /// Loads the replay called `name` from the replay directory, the error is a message for the
+    /// player
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(name: &str) -> Result<Replay, String> {
+        Replay::load_file(&std::path::Path::new(REPLAY_DIRECTORY).join(name))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_name: &str) -> Result<Replay, String> {
+        Err("Replays are not stored on the web".to_string())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(path: &std::path::Path) -> Result<Replay, String> {
+        let replay: Replay = std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| ron::from_str(&contents).map_err(|error| error.to_string()))?;
+        if replay.version != REPLAY_VERSION {
+            return Err("It was recorded by another version of the game".to_string());
+        }
+        Ok(replay)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_file(_path: &std::path::Path) -> Result<Replay, String> {
+        Err("Replays are not stored on the web".to_string())
+    }
+
+    /// Writes the replay into the replay directory, returns the name to load it by
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self) -> Option<String> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        let name = format!("{timestamp}.replay.ron");
+        let result = ron::to_string(self)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                std::fs::create_dir_all(REPLAY_DIRECTORY)
+                    .and_then(|_| {
+                        std::fs::write(std::path::Path::new(REPLAY_DIRECTORY).join(&name), contents)
+                    })
+                    .map_err(|error| error.to_string())
+            });
+        match result {
+            Ok(()) => Some(name),
+            Err(error) => {
+                warn!("Failed to save replay {error}");
+                None
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save(&self) -> Option<String> {
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn delete(name: &str) {
+        if let Err(error) = std::fs::remove_file(std::path::Path::new(REPLAY_DIRECTORY).join(name))
+        {
+            warn!("Failed to delete replay {name} {error}");
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn delete(_name: &str) {}
+}
+
+/// The input of the round being played
+#[derive(Resource, Default)]
+pub struct ReplayRecording(pub Replay);
+
+/// A replay being watched, insert it with [`ReplayPlayback::new`] before entering
+/// [`GameState::Playing`]. It is removed once the round's results are left.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    run: usize,
+    step: u32,
+    /// Restored when the playback ends
+    previous_difficulty: DifficultyPreset,
+    previous_mode: GameMode,
+    pub(crate) previous_tuning: Tuning,
+    pub(crate) previous_layout: ArenaLayout,
+}
+
+impl ReplayPlayback {
+    /// Switches the difficulty, game mode, tuning and arena to the ones the replay was recorded with
+    pub fn new(
+        replay: Replay,
+        difficulty: &mut Difficulty,
+        game_mode: &mut GameMode,
+        tuning: &mut Tuning,
+        layout: &mut ArenaLayout,
+    ) -> Self {
+        // Only classic rounds are recorded
+        let previous_mode = std::mem::replace(game_mode, GameMode::Classic);
+        let previous_difficulty = difficulty.preset;
+        difficulty.preset = replay.difficulty;
+        difficulty.settings = replay.settings;
+        let previous_tuning = std::mem::replace(tuning, replay.tuning.clone());
+        let previous_layout = std::mem::replace(layout, replay.arena.clone());
+        ReplayPlayback {
+            replay,
+            run: 0,
+            step: 0,
+            previous_difficulty,
+            previous_mode,
+            previous_tuning,
+            previous_layout,
+        }
+    }
+
+    /// The input of the next fixed step, `None` once the replay is over
+    fn next_input(&mut self) -> Option<InputRun> {
+        while let Some(run) = self.replay.input.get(self.run) {
+            if self.step < run.steps {
+                self.step += 1;
+                return Some(*run);
+            }
+            self.run += 1;
+            self.step = 0;
+        }
+        None
+    }
+}
+
+fn start_recording(
+    game_rng: Res<GameRng>,
+    difficulty: Res<Difficulty>,
+    tuning: Res<Tuning>,
+    layout: Res<ArenaLayout>,
+    mut recording: ResMut<ReplayRecording>,
+) {
+    recording.0 = Replay {
+        seed: game_rng.seed,
+        difficulty: difficulty.preset,
+        settings: difficulty.settings,
+        tuning: tuning.clone(),
+        arena: layout.clone(),
+        ..default()
+    };
+}
+
+fn start_playback(
+    mut game_rng: ResMut<GameRng>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    game_rng.reseed_with(playback.replay.seed);
+    playback.run = 0;
+    playback.step = 0;
+}
+
+fn record_input(
+    actions: Res<Actions>,
+    mut recording: ResMut<ReplayRecording>,
+) {
+    recording
+        .0
+        .push(actions.p1_movement, actions.p1_bee_movement);
+}
+
+fn play_back_input(
+    mut actions: ResMut<Actions>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let input = playback.next_input();
+    if input.is_none() {
+        // The recording stopped before the queen ran out of lives
+        next_state.set(GameState::GameOver);
+    }
+    actions.p1_movement = input.map_or(Vec2::ZERO, |input| input.movement);
+    actions.p1_bee_movement = input.map_or(Vec2::ZERO, |input| input.bee_movement);
+}
+
+fn stop_playback(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut difficulty: ResMut<Difficulty>,
+    mut game_mode: ResMut<GameMode>,
+    mut tuning: ResMut<Tuning>,
+    mut layout: ResMut<ArenaLayout>,
+) {
+    difficulty.select(playback.previous_difficulty);
+    *game_mode = playback.previous_mode;
+    *tuning = playback.previous_tuning.clone();
+    *layout = playback.previous_layout.clone();
+    commands.remove_resource::<ReplayPlayback>();
+}
@@ -0,0 +1,230 @@
+use crate::arena::ArenaLayout;
+use crate::difficulty::Difficulty;
+use crate::high_scores::HighScores;
+use crate::menu::{ButtonColors, ChangeState};
+use crate::replay::{Replay, ReplayPlayback};
+use crate::time_attack::GameMode;
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+
+pub struct ReplayBrowserPlugin;
+
+/// This plugin lists the replays of the high score table, opened from the main menu
+/// Picking one watches it, and a label marks the round as a replay while it plays
+impl Plugin for ReplayBrowserPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_systems(OnEnter(GameState::Replays), setup_browser)
+            .add_systems(Update, watch_replay.run_if(in_state(GameState::Replays)))
+            .add_systems(OnExit(GameState::Replays), cleanup_browser)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                spawn_replay_label.run_if(resource_exists::<ReplayPlayback>()),
+            );
+    }
+}
+
+#[derive(Component)]
+struct ReplayBrowser;
+
+#[derive(Component)]
+struct WatchReplay(String);
+
+/// Tells the player why the picked replay can't be watched
+#[derive(Component)]
+struct ReplayError;
+
+fn button(colors: &ButtonColors) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            width: Val::Px(400.0),
+            height: Val::Px(40.0),
+            margin: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: colors.normal.into(),
+        ..default()
+    }
+}
+
+fn button_text(text: String) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size: 22.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    )
+}
+
+fn setup_browser(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ReplayBrowser,
+        ))
+        .with_children(|children| {
+            children.spawn(
+                TextBundle::from_section(
+                    "Replays",
+                    TextStyle {
+                        font: default(),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                }),
+            );
+
+            let mut listed = 0;
+            for (index, high_score) in high_scores.entries.iter().enumerate() {
+                let Some(replay) = &high_score.replay else {
+                    continue;
+                };
+                listed += 1;
+                let colors = ButtonColors::default();
+                children
+                    .spawn((button(&colors), colors, WatchReplay(replay.clone())))
+                    .with_children(|parent| {
+                        parent.spawn(button_text(format!(
+                            "{}. {:.0} points, {} bees ({})",
+                            index + 1,
+                            high_score.points,
+                            high_score.bees,
+                            high_score.difficulty.name()
+                        )));
+                    });
+            }
+            if listed == 0 {
+                children.spawn(TextBundle::from_section(
+                    "Set a high score to record a replay",
+                    TextStyle {
+                        font: default(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ));
+            }
+
+            children.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: default(),
+                        font_size: 20.0,
+                        color: Color::rgb(1.0, 0.5, 0.5),
+                    },
+                ),
+                ReplayError,
+            ));
+
+            children.spawn(NodeBundle {
+                style: Style {
+                    height: Val::Px(30.0),
+                    ..default()
+                },
+                ..default()
+            });
+            let colors = ButtonColors::default();
+            children
+                .spawn((button(&colors), colors, ChangeState(GameState::Menu)))
+                .with_children(|parent| {
+                    parent.spawn(button_text("Back to Menu".to_string()));
+                });
+        });
+}
+
+fn watch_replay(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut game_mode: ResMut<GameMode>,
+    mut tuning: ResMut<Tuning>,
+    mut layout: ResMut<ArenaLayout>,
+    interaction_query: Query<(&Interaction, &WatchReplay), Changed<Interaction>>,
+    mut error_text: Query<&mut Text, With<ReplayError>>,
+) {
+    for (interaction, watch) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let replay = match Replay::load(&watch.0) {
+            Ok(replay) => replay,
+            Err(error) => {
+                warn!("Failed to load replay {} {error}", watch.0);
+                for mut text in &mut error_text {
+                    text.sections[0].value = format!("Can't watch this replay. {error}");
+                }
+                continue;
+            }
+        };
+        commands.insert_resource(ReplayPlayback::new(
+            replay,
+            &mut difficulty,
+            &mut game_mode,
+            &mut tuning,
+            &mut layout,
+        ));
+        next_state.set(GameState::Playing);
+        return;
+    }
+}
+
+/// Centered below the HUD bar, so it covers none of the HUD texts
+fn spawn_replay_label(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(30.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            RoundEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Replay",
+                TextStyle {
+                    font: default(),
+                    font_size: 28.0,
+                    color: Color::rgb(1.0, 0.85, 0.2),
+                },
+            ));
+        });
+}
+
+fn cleanup_browser(
+    mut commands: Commands,
+    browser: Query<Entity, With<ReplayBrowser>>,
+) {
+    for entity in browser.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
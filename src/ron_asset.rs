@@ -0,0 +1,92 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{Asset, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::marker::PhantomData;
+
+/// This plugin registers an asset loader that deserializes `A` from RON files
+/// The loader is picked by the (possibly multi part) file extensions passed to [`RonAssetPlugin::new`]
+pub struct RonAssetPlugin<A> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> RonAssetPlugin<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        RonAssetPlugin {
+            extensions,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A> Plugin for RonAssetPlugin<A>
+where
+    A: Asset + for<'de> Deserialize<'de>,
+{
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_asset::<A>()
+            .register_asset_loader(RonAssetLoader::<A> {
+                extensions: self.extensions,
+                _marker: PhantomData,
+            });
+    }
+}
+
+struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<fn() -> A>,
+}
+
+#[derive(Debug)]
+pub enum RonAssetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl std::fmt::Display for RonAssetError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            RonAssetError::Io(error) => write!(f, "could not read file: {error}"),
+            RonAssetError::Ron(error) => write!(f, "could not parse RON: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetError {}
+
+impl<A> AssetLoader for RonAssetLoader<A>
+where
+    A: Asset + for<'de> Deserialize<'de>,
+{
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<A, RonAssetError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(RonAssetError::Io)?;
+            ron::de::from_bytes::<A>(&bytes).map_err(RonAssetError::Ron)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
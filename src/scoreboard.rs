@@ -1,19 +1,39 @@
-use crate::GameState;
+use crate::bees::QueenBee;
+use crate::events::{FlowerCollected, RoundEnded, WorkerBeeDied, WorkerBeeSpawned};
+use crate::health::Lives;
+use crate::{GameState, RoundEntity};
 use bevy::prelude::*;
 
+pub struct ScorePlugin;
+
+/// This plugin keeps the [`Score`] of the current round up to date from the gameplay events
+impl Plugin for ScorePlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<Score>()
+            .add_systems(OnEnter(GameState::Playing), reset_score)
+            .add_systems(OnEnter(GameState::GameOver), end_round)
+            .add_systems(Update, update_score.run_if(in_state(GameState::Playing)));
+    }
+}
+
 #[derive(Default)]
 pub struct ScoreboardPlugin;
 
+/// This plugin shows the score, lives and worker bees of the current round
 impl Plugin for ScoreboardPlugin {
     fn build(
         &self,
         app: &mut App,
     ) {
-        app.init_resource::<Score>()
-            .add_systems(OnEnter(GameState::Playing), setup)
+        app.add_systems(OnEnter(GameState::Playing), setup)
             .add_systems(
                 Update,
-                update_score_text.run_if(in_state(GameState::Playing)),
+                update_score_text
+                    .after(update_score)
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -24,53 +44,104 @@ struct ScoreText;
 #[derive(Component)]
 struct BeeText;
 
+#[derive(Component)]
+struct LivesText;
+
 #[derive(Default, Resource)]
 pub struct Score {
     pub points: f32,
     pub bees: usize,
 }
 
+/// The HUD is a single bar across the top of the screen, sized in logical pixels so that it
+/// follows the [`UiScale`] set by the camera plugin
 fn setup(mut commands: Commands) {
-    commands.spawn((
-        TextBundle::from_section(
-            "Score: ",
-            TextStyle {
-                font: default(),
-                font_size: 20.0,
-                color: Color::WHITE,
-            },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(3.0),
-            left: Val::Px(8.0),
-            ..default()
-        }),
-        ScoreText,
-    ));
-    commands.spawn((
-        TextBundle::from_section(
-            "Worker Bees: ",
-            TextStyle {
-                font: default(),
-                font_size: 20.0,
-                color: Color::rgb(0.9, 0.9, 0.9),
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(3.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
             },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(3.0),
-            right: Val::Px(8.0),
-            ..default()
-        }),
-        BeeText,
-    ));
+            RoundEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Score: ",
+                    TextStyle {
+                        font: default(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ScoreText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "Lives: ",
+                    TextStyle {
+                        font: default(),
+                        font_size: 20.0,
+                        color: Color::rgb(1.0, 0.8, 0.8),
+                    },
+                ),
+                LivesText,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "Worker Bees: ",
+                    TextStyle {
+                        font: default(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ),
+                BeeText,
+            ));
+        });
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn update_score(
+    mut score: ResMut<Score>,
+    mut collected_events: EventReader<FlowerCollected>,
+    mut spawned_events: EventReader<WorkerBeeSpawned>,
+    mut died_events: EventReader<WorkerBeeDied>,
+) {
+    for collected in collected_events.read() {
+        score.points += collected.score_delta;
+    }
+    score.bees += spawned_events.read().count();
+    score.bees = score.bees.saturating_sub(died_events.read().count());
+}
+
+pub fn end_round(
+    score: Res<Score>,
+    mut round_ended_events: EventWriter<RoundEnded>,
+) {
+    round_ended_events.send(RoundEnded {
+        points: score.points,
+        bees: score.bees,
+    });
 }
 
 fn update_score_text(
     score: Res<Score>,
-    mut score_query: Query<&mut Text, (With<ScoreText>, Without<BeeText>)>,
-    mut bee_query: Query<&mut Text, (With<BeeText>, Without<ScoreText>)>,
+    queen_query: Query<&Lives, With<QueenBee>>,
+    mut score_query: Query<&mut Text, (With<ScoreText>, Without<BeeText>, Without<LivesText>)>,
+    mut bee_query: Query<&mut Text, (With<BeeText>, Without<ScoreText>, Without<LivesText>)>,
+    mut lives_query: Query<&mut Text, (With<LivesText>, Without<ScoreText>, Without<BeeText>)>,
 ) {
     let points = score.points;
     for mut text in &mut score_query {
@@ -81,4 +152,10 @@ fn update_score_text(
     for mut text in &mut bee_query {
         text.sections[0].value = format!("Worker Bees: {}", bees);
     }
+
+    if let Ok(lives) = queen_query.get_single() {
+        for mut text in &mut lives_query {
+            text.sections[0].value = format!("Lives: {}", lives.0);
+        }
+    }
 }
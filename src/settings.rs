@@ -0,0 +1,76 @@
+use crate::audio::MixerChannel;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct SettingsPlugin;
+
+/// This plugin loads the player's [`Settings`]
+/// On native platforms they are read from a RON file next to the game, missing fields keep their defaults
+impl Plugin for SettingsPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.insert_resource(Settings::load())
+            .register_type::<Settings>();
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_PATH: &str = "settings.ron";
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Scales every mixer channel
+    pub master_volume: f64,
+    pub music_volume: f64,
+    pub sfx_volume: f64,
+    pub ui_volume: f64,
+    pub ambient_volume: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            ui_volume: 1.0,
+            ambient_volume: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    /// The volume of a mixer channel, including the master volume
+    pub fn channel_volume(
+        &self,
+        channel: MixerChannel,
+    ) -> f64 {
+        let volume = match channel {
+            MixerChannel::Music => self.music_volume,
+            MixerChannel::Sfx => self.sfx_volume,
+            MixerChannel::Ui => self.ui_volume,
+            MixerChannel::Ambient => self.ambient_volume,
+        };
+        (self.master_volume * volume).clamp(0.0, 1.0)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Self {
+        match std::fs::read_to_string(SETTINGS_PATH) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(|error| {
+                warn!("Failed to parse settings {error}");
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load() -> Self {
+        Settings::default()
+    }
+}
@@ -0,0 +1,202 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::bees::QueenBee;
+use crate::events::FlowerCollected;
+use crate::tuning::Tuning;
+use crate::{GameState, FIXED_TIMESTEP_HZ};
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+
+pub struct TimeAttackPlugin;
+
+/// This plugin runs the clock of time attack rounds. They end as soon as the queen has collected
+/// [`TIME_ATTACK_POINTS`], and the time is counted in fixed steps so it doesn't depend on the
+/// frame rate. The queen's path is recorded into a [`PositionTrack`] along the way.
+impl Plugin for TimeAttackPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<GameMode>()
+            .init_resource::<TimeAttack>()
+            .add_systems(OnEnter(GameState::Playing), reset_time_attack)
+            .add_systems(
+                FixedUpdate,
+                run_clock.after(PhysicsSet::Sync).run_if(
+                    in_state(GameState::Playing).and_then(resource_equals(GameMode::TimeAttack)),
+                ),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    /// Play until the queen runs out of lives
+    #[default]
+    Classic,
+    /// Collect the points of [`Tuning::time_attack_points`] as fast as possible
+    TimeAttack,
+}
+
+impl GameMode {
+    pub fn next(&self) -> GameMode {
+        match self {
+            GameMode::Classic => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Classic,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::TimeAttack => "Time Attack",
+        }
+    }
+}
+
+/// The clock and path of the current time attack round
+#[derive(Resource, Default)]
+pub struct TimeAttack {
+    /// Fixed steps since the round started
+    pub steps: u32,
+    pub points: f32,
+    /// Steps it took to collect the points, `None` until the round is finished
+    pub finished: Option<u32>,
+    pub track: PositionTrack,
+}
+
+/// Converts a number of fixed steps into seconds
+pub fn steps_to_seconds(steps: f32) -> f32 {
+    steps / FIXED_TIMESTEP_HZ as f32
+}
+
+fn reset_time_attack(mut time_attack: ResMut<TimeAttack>) {
+    *time_attack = TimeAttack::default();
+}
+
+fn run_clock(
+    tuning: Res<Tuning>,
+    mut time_attack: ResMut<TimeAttack>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut collected_events: EventReader<FlowerCollected>,
+    queen_query: Query<&Position, With<QueenBee>>,
+) {
+    if time_attack.finished.is_some() {
+        return;
+    }
+    if let Ok(position) = queen_query.get_single() {
+        let steps = time_attack.steps;
+        time_attack
+            .track
+            .record(steps, Vec2::new(position.x as f32, position.y as f32));
+    }
+    time_attack.steps += 1;
+    for collected in collected_events.read() {
+        time_attack.points += collected.score_delta;
+    }
+    if time_attack.points >= tuning.time_attack_points {
+        let steps = time_attack.steps;
+        time_attack.finished = Some(steps);
+        time_attack.track.finish_steps = steps;
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Fixed steps between two samples of a [`PositionTrack`]
+const TRACK_SAMPLE_INTERVAL: u32 = 4;
+/// Samples are stored in fractions of a world unit
+const TRACK_PRECISION: f32 = 8.0;
+const TRACK_MAGIC: &[u8; 4] = b"QBGT";
+const TRACK_VERSION: u8 = 1;
+
+/// The path of a body, sampled every few fixed steps
+///
+/// Stored as the magic bytes `QBGT`, a version byte, the sample interval as a `u16`, the finish
+/// time in steps and the number of samples as `u32`, followed by every sample as two `i16` in
+/// eighths of a world unit. All numbers are little endian.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionTrack {
+    pub sample_interval: u32,
+    /// Steps the run took
+    pub finish_steps: u32,
+    pub samples: Vec<Vec2>,
+}
+
+impl Default for PositionTrack {
+    fn default() -> Self {
+        PositionTrack {
+            sample_interval: TRACK_SAMPLE_INTERVAL,
+            finish_steps: 0,
+            samples: Vec::new(),
+        }
+    }
+}
+
+impl PositionTrack {
+    fn record(
+        &mut self,
+        step: u32,
+        position: Vec2,
+    ) {
+        if step % self.sample_interval == 0 {
+            self.samples.push(position);
+        }
+    }
+
+    /// The position `steps` into the run, between the two closest samples
+    pub fn position_at(
+        &self,
+        steps: f32,
+    ) -> Option<Vec2> {
+        let sample = (steps / self.sample_interval as f32).max(0.0);
+        let index = sample.floor() as usize;
+        let first = self.samples.get(index).or(self.samples.last())?;
+        let second = self.samples.get(index + 1).unwrap_or(first);
+        Some(first.lerp(*second, sample.fract()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(15 + self.samples.len() * 4);
+        bytes.extend_from_slice(TRACK_MAGIC);
+        bytes.push(TRACK_VERSION);
+        bytes.extend_from_slice(&(self.sample_interval as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.finish_steps.to_le_bytes());
+        bytes.extend_from_slice(&(self.samples.len() as u32).to_le_bytes());
+        for sample in &self.samples {
+            for value in [sample.x, sample.y] {
+                let value = (value * TRACK_PRECISION)
+                    .round()
+                    .clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// `None` if the bytes aren't a track of this version
+    pub fn from_bytes(bytes: &[u8]) -> Option<PositionTrack> {
+        let (header, body) = (bytes.get(..15)?, bytes.get(15..)?);
+        if &header[..4] != TRACK_MAGIC || header[4] != TRACK_VERSION {
+            return None;
+        }
+        let sample_interval = u16::from_le_bytes(header[5..7].try_into().ok()?) as u32;
+        let finish_steps = u32::from_le_bytes(header[7..11].try_into().ok()?);
+        let count = u32::from_le_bytes(header[11..15].try_into().ok()?) as usize;
+        if sample_interval == 0 || body.len() != count * 4 {
+            return None;
+        }
+        let samples = body
+            .chunks_exact(4)
+            .map(|chunk| {
+                let x = i16::from_le_bytes([chunk[0], chunk[1]]);
+                let y = i16::from_le_bytes([chunk[2], chunk[3]]);
+                Vec2::new(x as f32, y as f32) / TRACK_PRECISION
+            })
+            .collect();
+        Some(PositionTrack {
+            sample_interval,
+            finish_steps,
+            samples,
+        })
+    }
+}
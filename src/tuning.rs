@@ -0,0 +1,154 @@
+use crate::difficulty::{Difficulty, DifficultyPresets};
+use crate::flowers::FlowerTuning;
+use crate::loading::TuningAssets;
+use crate::pickups::PickupTuning;
+use crate::replay::ReplayPlayback;
+use crate::ron_asset::RonAssetPlugin;
+use crate::wasps::WaspTuning;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub struct TuningPlugin;
+
+/// This plugin loads the [`Tuning`] from `assets/tuning.ron` and applies it whenever the file
+/// changes. Assets are only watched for changes with the `dev` feature, so designers can iterate on
+/// the file with `cargo run --features dev` while the game is running. Changes made during a round
+/// are applied once it is over, so the round plays out and records with a single tuning.
+impl Plugin for TuningPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.add_plugins(RonAssetPlugin::<Tuning>::new(&["tuning.ron"]))
+            .add_systems(OnExit(GameState::Loading), apply_tuning)
+            .add_systems(
+                Update,
+                (
+                    reload_tuning.run_if(resource_exists::<TuningAssets>()),
+                    update_clear_color.run_if(resource_changed::<Tuning>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The gameplay constants that designers tune, every field falls back to its default when it is
+/// missing from the file
+#[derive(Asset, Resource, Clone, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Tuning {
+    /// Size of the playable area inside the walls, in world units
+    pub arena_size: Vec2,
+    pub wall_thickness: f32,
+    /// Collider radius of the queen
+    pub queen_radius: f32,
+    /// Collider radius of a worker bee
+    pub worker_radius: f32,
+    /// Lives the queen starts a round with
+    pub starting_lives: u32,
+    /// Seconds the queen can't be hurt again after a hit
+    pub invulnerable_time: f32,
+    /// Points the queen has to collect to finish a time attack round
+    pub time_attack_points: f32,
+    pub pickups: PickupTuning,
+    /// How flowers grow and what every kind of flower does
+    pub flowers: FlowerTuning,
+    pub wasps: WaspTuning,
+    /// Movement scalings and physics substeps of the difficulty presets
+    pub difficulty: DifficultyPresets,
+    pub clear_color: Color,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            arena_size: Vec2::new(800.0, 560.0),
+            wall_thickness: 50.0,
+            queen_radius: 30.0,
+            worker_radius: 7.0,
+            starting_lives: 3,
+            invulnerable_time: 2.0,
+            time_attack_points: 20.0,
+            pickups: PickupTuning::default(),
+            flowers: FlowerTuning::default(),
+            wasps: WaspTuning::default(),
+            difficulty: DifficultyPresets::default(),
+            clear_color: Color::rgb(0.161, 0.678, 1.0),
+        }
+    }
+}
+
+/// Deserializes a field of a partial entry in the file, missing fields stay `None` through
+/// `#[serde(default)]` and are filled in from the defaults of that entry
+pub(crate) fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl Tuning {
+    /// The world units that are always visible, the arena plus its walls
+    pub fn view_size(&self) -> Vec2 {
+        self.arena_size + 2.0 * self.wall_thickness
+    }
+}
+
+fn apply_tuning(
+    tuning_assets: Res<TuningAssets>,
+    assets: Res<Assets<Tuning>>,
+    mut tuning: ResMut<Tuning>,
+    mut difficulty: ResMut<Difficulty>,
+    mut playback: Option<ResMut<ReplayPlayback>>,
+) {
+    let Some(loaded) = assets.get(&tuning_assets.tuning) else {
+        return;
+    };
+    difficulty.presets = loaded.difficulty;
+    if let Some(custom) = loaded.difficulty.custom {
+        difficulty.custom = custom;
+    }
+    match playback.as_deref_mut() {
+        // A replay keeps the tuning it was recorded with until it is over
+        Some(playback) => playback.previous_tuning = loaded.clone(),
+        None => {
+            *tuning = loaded.clone();
+            let preset = difficulty.preset;
+            difficulty.select(preset);
+        }
+    }
+    info!("Applied the gameplay tuning");
+}
+
+fn reload_tuning(
+    mut asset_events: EventReader<AssetEvent<Tuning>>,
+    mut reload_pending: Local<bool>,
+    state: Res<State<GameState>>,
+    tuning_assets: Res<TuningAssets>,
+    assets: Res<Assets<Tuning>>,
+    tuning: ResMut<Tuning>,
+    difficulty: ResMut<Difficulty>,
+    playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if asset_events
+        .read()
+        .any(|event| event.is_modified(&tuning_assets.tuning))
+    {
+        *reload_pending = true;
+    }
+    // Changing the tuning in the middle of a round would make its replay play back differently
+    if *reload_pending && *state.get() != GameState::Playing {
+        *reload_pending = false;
+        apply_tuning(tuning_assets, assets, tuning, difficulty, playback);
+    }
+}
+
+fn update_clear_color(
+    tuning: Res<Tuning>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    clear_color.0 = tuning.clear_color;
+}
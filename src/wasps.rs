@@ -0,0 +1,325 @@
+#![allow(clippy::unnecessary_cast)]
+
+use crate::bees::{Layer, QueenBee, WorkerBee};
+use crate::events::{DeathCause, WorkerBeeDied};
+use crate::game_rng::GameRng;
+use crate::health::{DamageSource, GodMode, Invulnerable, QueenDamaged};
+use crate::interpolation::Interpolated;
+use crate::loading::TextureAssets;
+use crate::tuning::Tuning;
+use crate::{GameState, RoundEntity};
+use bevy::prelude::*;
+use bevy_xpbd_2d::{math::*, prelude::*};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct WaspsPlugin;
+
+/// This plugin spawns hostile wasps according to the schedule in the [`WaspTuning`]. Wasps patrol
+/// the arena, chase the queen when she comes close, hurt her on contact and kill any worker bee
+/// they touch.
+impl Plugin for WaspsPlugin {
+    fn build(
+        &self,
+        app: &mut App,
+    ) {
+        app.init_resource::<WaspSpawner>()
+            .add_systems(OnEnter(GameState::Playing), reset_wasp_spawner)
+            .add_systems(
+                FixedUpdate,
+                (spawn_wasps, wasp_ai)
+                    .chain()
+                    .before(PhysicsSet::Prepare)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                face_wasps
+                    .run_if(in_state(GameState::Playing).or_else(in_state(GameState::GameOver))),
+            )
+            .add_systems(
+                PostProcessCollisions,
+                wasp_collision.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Half the size of the area that wasps enter and patrol in
+pub fn wasp_area(tuning: &Tuning) -> Vec2 {
+    tuning.arena_size / 2.0 - tuning.wasps.margin
+}
+
+/// When wasps show up and how they fly, part of the [`Tuning`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct WaspTuning {
+    /// Seconds into a round and how many wasps may be alive from then on
+    pub schedule: Vec<(f32, usize)>,
+    /// Seconds between two wasps entering the arena
+    pub spawn_interval: f32,
+    /// Space kept free between the wasp patrols and the walls
+    pub margin: Vec2,
+    /// Collider radius of a wasp
+    pub radius: f32,
+    pub patrol_speed: f32,
+    pub chase_speed: f32,
+    /// Speed of a wasp flying away after it stung the queen
+    pub flee_speed: f32,
+    /// How quickly a wasp turns towards where it wants to fly
+    pub steering: f32,
+    /// A patrolling wasp starts chasing the queen when she comes this close
+    pub chase_range: f32,
+    /// A chasing wasp gives up once the queen is this far away
+    pub give_up_range: f32,
+    /// Seconds a wasp flees after it stung the queen
+    pub flee_time: f32,
+}
+
+impl Default for WaspTuning {
+    fn default() -> Self {
+        WaspTuning {
+            schedule: vec![(20.0, 1), (45.0, 2), (90.0, 3), (150.0, 4)],
+            spawn_interval: 8.0,
+            margin: Vec2::new(50.0, 80.0),
+            radius: 12.0,
+            patrol_speed: 120.0,
+            chase_speed: 220.0,
+            flee_speed: 260.0,
+            steering: 4.0,
+            chase_range: 200.0,
+            give_up_range: 320.0,
+            flee_time: 2.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WaspState {
+    Patrol,
+    Chase,
+    Flee,
+}
+
+#[derive(Component)]
+pub struct Wasp {
+    pub state: WaspState,
+    patrol_target: Vec2,
+    flee_timer: Timer,
+}
+
+impl Wasp {
+    fn new(
+        patrol_target: Vec2,
+        flee_time: f32,
+    ) -> Self {
+        Wasp {
+            state: WaspState::Patrol,
+            patrol_target,
+            flee_timer: Timer::from_seconds(flee_time, TimerMode::Once),
+        }
+    }
+
+    fn flee(&mut self) {
+        self.state = WaspState::Flee;
+        self.flee_timer.reset();
+    }
+}
+
+#[derive(Resource, Default)]
+struct WaspSpawner {
+    round_time: f32,
+    timer: Timer,
+}
+
+impl WaspSpawner {
+    fn max_wasps(
+        &self,
+        schedule: &[(f32, usize)],
+    ) -> usize {
+        schedule
+            .iter()
+            .rev()
+            .find(|(time, _)| self.round_time >= *time)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+fn reset_wasp_spawner(
+    tuning: Res<Tuning>,
+    mut spawner: ResMut<WaspSpawner>,
+) {
+    spawner.round_time = 0.0;
+    spawner.timer = Timer::from_seconds(tuning.wasps.spawn_interval, TimerMode::Repeating);
+}
+
+fn random_wasp_position(
+    rng: &mut impl Rng,
+    area: Vec2,
+) -> Vec2 {
+    Vec2::new(
+        rng.gen_range(-area.x..area.x),
+        rng.gen_range(-area.y..area.y),
+    )
+}
+
+fn spawn_wasps(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    tuning: Res<Tuning>,
+    time: Res<Time>,
+    mut game_rng: ResMut<GameRng>,
+    mut spawner: ResMut<WaspSpawner>,
+    wasps: Query<(), With<Wasp>>,
+    queen_query: Query<&Transform, With<QueenBee>>,
+) {
+    spawner.round_time += time.delta_seconds();
+    if !spawner.timer.tick(time.delta()).just_finished()
+        || wasps.iter().count() >= spawner.max_wasps(&tuning.wasps.schedule)
+    {
+        return;
+    }
+
+    let rng = game_rng.rng();
+    // Wasps enter on the side of the arena facing away from the queen
+    let queen_x = queen_query
+        .get_single()
+        .map_or(0.0, |transform| transform.translation.x);
+    let side = if queen_x > 0.0 { -1.0 } else { 1.0 };
+    let area = wasp_area(&tuning);
+    let position = Vec2::new(side * area.x, rng.gen_range(-area.y..area.y));
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position.extend(1.))),
+            RigidBody::Dynamic,
+            Collider::ball(tuning.wasps.radius as Scalar),
+            LockedAxes::ROTATION_LOCKED,
+            CollisionLayers::new([Layer::Wasp], [Layer::Queen, Layer::Worker, Layer::Wall]),
+            Wasp::new(random_wasp_position(rng, area), tuning.wasps.flee_time),
+            Interpolated::new(position),
+            RoundEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn(SpriteBundle {
+                texture: textures.wasp.clone(),
+                ..default()
+            });
+        });
+}
+
+fn wasp_ai(
+    time: Res<Time>,
+    tuning: Res<Tuning>,
+    mut game_rng: ResMut<GameRng>,
+    queen_query: Query<(&Transform, Has<Invulnerable>), With<QueenBee>>,
+    mut wasps: Query<(&Transform, &mut LinearVelocity, &mut Wasp)>,
+) {
+    let delta_time = time.delta_seconds();
+    let Ok((queen_transform, queen_invulnerable)) = queen_query.get_single() else {
+        return;
+    };
+    let queen_position = queen_transform.translation.truncate();
+    let rng = game_rng.rng();
+    let area = wasp_area(&tuning);
+    let wasp_tuning = &tuning.wasps;
+
+    for (transform, mut linear_velocity, mut wasp) in &mut wasps {
+        let position = transform.translation.truncate();
+        let queen_distance = position.distance(queen_position);
+
+        let state = wasp.state;
+        wasp.state = match state {
+            WaspState::Patrol
+                if queen_distance < wasp_tuning.chase_range && !queen_invulnerable =>
+            {
+                WaspState::Chase
+            }
+            WaspState::Chase
+                if queen_distance > wasp_tuning.give_up_range || queen_invulnerable =>
+            {
+                WaspState::Patrol
+            }
+            WaspState::Flee if wasp.flee_timer.tick(time.delta()).finished() => WaspState::Patrol,
+            state => state,
+        };
+
+        let desired = match wasp.state {
+            WaspState::Patrol => {
+                if position.distance(wasp.patrol_target) < wasp_tuning.radius * 2.0 {
+                    wasp.patrol_target = random_wasp_position(rng, area);
+                }
+                (wasp.patrol_target - position).normalize_or_zero()
+                    * wasp_tuning.patrol_speed as Scalar
+            }
+            WaspState::Chase => {
+                (queen_position - position).normalize_or_zero() * wasp_tuning.chase_speed as Scalar
+            }
+            WaspState::Flee => {
+                (position - queen_position).normalize_or_zero() * wasp_tuning.flee_speed as Scalar
+            }
+        };
+
+        let steering = (wasp_tuning.steering * delta_time).min(1.0);
+        linear_velocity.0 = linear_velocity.0.lerp(desired, steering);
+    }
+}
+
+/// The sprite is a child of the wasp's body and faces the way it flies
+fn face_wasps(
+    wasps: Query<(&LinearVelocity, &Children), With<Wasp>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    for (linear_velocity, children) in &wasps {
+        let mut iter = sprites.iter_many_mut(children);
+        while let Some(mut sprite) = iter.fetch_next() {
+            sprite.flip_x = linear_velocity.x < 0.0;
+        }
+    }
+}
+
+pub fn wasp_collision(
+    mut commands: Commands,
+    mut collision_event_reader: EventReader<CollisionStarted>,
+    mut damage_events: EventWriter<QueenDamaged>,
+    mut died_events: EventWriter<WorkerBeeDied>,
+    god_mode: Res<GodMode>,
+    queen_query: Query<Has<Invulnerable>, With<QueenBee>>,
+    worker_query: Query<&Transform, With<WorkerBee>>,
+    mut wasps: Query<&mut Wasp>,
+) {
+    let mut killed: Vec<Entity> = Vec::new();
+    // Only one hit a step costs a life, the wasp that landed it flees
+    let mut stung = false;
+
+    for CollisionStarted(entity1, entity2) in collision_event_reader.read() {
+        let (wasp_entity, other) = if wasps.contains(*entity1) {
+            (*entity1, *entity2)
+        } else if wasps.contains(*entity2) {
+            (*entity2, *entity1)
+        } else {
+            continue;
+        };
+
+        if let Ok(worker_transform) = worker_query.get(other) {
+            if killed.contains(&other) {
+                continue;
+            }
+            killed.push(other);
+            commands.entity(other).despawn_recursive();
+            died_events.send(WorkerBeeDied {
+                position: worker_transform.translation.truncate(),
+                cause: DeathCause::Wasp,
+            });
+        } else if let Ok(invulnerable) = queen_query.get(other) {
+            if invulnerable || god_mode.0 || stung {
+                continue;
+            }
+            stung = true;
+            damage_events.send(QueenDamaged {
+                source: DamageSource::Wasp,
+            });
+            if let Ok(mut wasp) = wasps.get_mut(wasp_entity) {
+                wasp.flee();
+            }
+        }
+    }
+}
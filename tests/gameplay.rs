@@ -0,0 +1,460 @@
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::Duration;
+use queen_beevy::bot::{BotPlugin, RoundStats};
+use queen_beevy::headless::{
+    count, headless_app, run_frames, run_until, start_replay, start_round, TIMESTEP,
+};
+use queen_beevy::launch::{LaunchOptions, LaunchPlugin};
+use queen_beevy::{
+    Actions, ArenaLayout, ArenaRect, CameraController, CameraPlugin, Difficulty, DifficultyPreset,
+    Flower, FlowerKind, GameMode, GameRng, GameState, InterpolationPlugin, PositionTrack,
+    Production, QueenBee, ReplayRecording, Score, TimeAttack, Tuning, WorkerBee, FIXED_TIMESTEP_HZ,
+};
+
+fn frames(seconds: f32) -> usize {
+    (seconds / TIMESTEP).round() as usize
+}
+
+fn flowers(app: &mut App) -> Vec<(Entity, FlowerKind, Vec2)> {
+    app.world
+        .query::<(Entity, &Flower, &Transform)>()
+        .iter(&app.world)
+        .map(|(entity, flower, transform)| (entity, flower.kind, transform.translation.truncate()))
+        .collect()
+}
+
+fn worker_bees_after(
+    preset: DifficultyPreset,
+    seconds: f32,
+) -> usize {
+    let mut app = headless_app();
+    app.world.resource_mut::<Difficulty>().select(preset);
+    start_round(&mut app);
+    run_frames(&mut app, frames(seconds));
+    count::<WorkerBee>(&mut app)
+}
+
+#[test]
+fn round_starts_with_a_queen_and_a_daisy() {
+    let mut app = headless_app();
+    start_round(&mut app);
+
+    assert_eq!(count::<QueenBee>(&mut app), 1);
+    assert_eq!(count::<WorkerBee>(&mut app), 0);
+    let flowers = flowers(&mut app);
+    assert_eq!(flowers.len(), 1);
+    assert_eq!(flowers[0].1, FlowerKind::Daisy);
+
+    let score = app.world.resource::<Score>();
+    assert_eq!(score.points, 0.0);
+    assert_eq!(score.bees, 0);
+}
+
+#[test]
+fn queen_produces_a_worker_bee_every_second() {
+    let mut app = headless_app();
+    start_round(&mut app);
+    run_frames(&mut app, frames(3.5));
+    // One more frame so the score has seen the last spawn event
+    app.update();
+
+    let workers = count::<WorkerBee>(&mut app);
+    assert_eq!(workers, 3);
+    assert_eq!(app.world.resource::<Score>().bees, workers);
+}
+
+#[test]
+fn harder_difficulties_spawn_more_worker_bees() {
+    let easy = worker_bees_after(DifficultyPreset::Easy, 5.0);
+    let normal = worker_bees_after(DifficultyPreset::Normal, 5.0);
+    let hard = worker_bees_after(DifficultyPreset::Hard, 5.0);
+
+    assert!(easy < normal, "easy {easy}, normal {normal}");
+    assert!(normal < hard, "normal {normal}, hard {hard}");
+}
+
+#[test]
+fn collecting_a_flower_scores_and_grows_a_new_one() {
+    let mut app = headless_app();
+    start_round(&mut app);
+    let (daisy, _, daisy_position) = flowers(&mut app)[0];
+
+    // The daisy is straight to the right of the queen
+    app.world.resource_mut::<Actions>().p1_movement = Vec2::X;
+    let collected = run_until(&mut app, frames(5.0), |world| {
+        world.get_entity(daisy).is_none()
+    });
+    assert!(collected, "the queen never reached the daisy");
+    app.world.resource_mut::<Actions>().p1_movement = Vec2::ZERO;
+    run_frames(&mut app, 2);
+
+    let score = app.world.resource::<Score>();
+    assert_eq!(score.points, 1.0);
+    let flowers = flowers(&mut app);
+    assert!(!flowers.is_empty());
+    assert!(flowers
+        .iter()
+        .all(|(entity, _, position)| *entity != daisy && *position != daisy_position));
+
+    let production = app
+        .world
+        .query_filtered::<&Production, With<QueenBee>>()
+        .single(&app.world)
+        .0;
+    assert!(production > 1.0);
+}
+
+/// Where everything is after playing a round with the given seed and a scripted input
+fn scripted_round(
+    seed: u64,
+    steps_per_frame: u32,
+    seconds: f32,
+) -> (Vec<Vec2>, f32) {
+    let mut app = headless_app();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        steps_per_frame as f64 / FIXED_TIMESTEP_HZ,
+    )));
+    app.world.resource_mut::<GameRng>().fixed_seed = Some(seed);
+    start_round(&mut app);
+
+    let steps_per_turn = frames(0.5) / steps_per_frame as usize * steps_per_frame as usize;
+    for step in (0..frames(seconds)).step_by(steps_per_frame as usize) {
+        // The input only changes on steps that start a frame at both frame rates
+        let turn = (step / steps_per_turn) as f32;
+        let mut actions = app.world.resource_mut::<Actions>();
+        actions.p1_movement = Vec2::from_angle(turn * 1.3);
+        actions.p1_bee_movement = Vec2::from_angle(turn * -0.7);
+        app.update();
+    }
+
+    let mut positions: Vec<Vec2> = app
+        .world
+        .query_filtered::<&Transform, Or<(With<QueenBee>, With<WorkerBee>, With<Flower>)>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation.truncate())
+        .collect();
+    positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    (positions, app.world.resource::<Score>().points)
+}
+
+#[test]
+fn a_seed_and_input_always_play_out_the_same_round() {
+    let first = scripted_round(7, 1, 25.0);
+    let second = scripted_round(7, 1, 25.0);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn the_frame_rate_does_not_change_the_round() {
+    let sixty = scripted_round(7, 1, 25.0);
+    let thirty = scripted_round(7, 2, 25.0);
+    assert_eq!(sixty, thirty);
+}
+
+fn queen_position(app: &mut App) -> Vec2 {
+    app.world
+        .query_filtered::<&Transform, With<QueenBee>>()
+        .single(&app.world)
+        .translation
+        .truncate()
+}
+
+#[test]
+fn a_recorded_round_plays_back_the_same() {
+    let mut app = headless_app();
+    app.world
+        .resource_mut::<Difficulty>()
+        .select(DifficultyPreset::Hard);
+    start_round(&mut app);
+    for frame in 0..frames(12.0) {
+        let turn = (frame / frames(0.4)) as f32;
+        let mut actions = app.world.resource_mut::<Actions>();
+        actions.p1_movement = Vec2::from_angle(turn * 2.1);
+        actions.p1_bee_movement = Vec2::from_angle(turn * 0.9);
+        app.update();
+    }
+    let replay = app.world.resource::<ReplayRecording>().0.clone();
+    // Fewer steps are recorded if the queen ran out of lives
+    assert!(replay.steps() > 0 && replay.steps() <= frames(12.0));
+    // Held input is stored once per run of steps
+    assert!(replay.input.len() <= frames(12.0) / frames(0.4) + 1);
+
+    let mut playback = headless_app();
+    start_replay(&mut playback, replay);
+    assert_eq!(
+        playback.world.resource::<Difficulty>().preset,
+        DifficultyPreset::Hard
+    );
+    run_frames(&mut playback, frames(12.0));
+
+    assert_eq!(queen_position(&mut playback), queen_position(&mut app));
+    assert_eq!(
+        count::<WorkerBee>(&mut playback),
+        count::<WorkerBee>(&mut app)
+    );
+    assert_eq!(
+        playback.world.resource::<Score>().points,
+        app.world.resource::<Score>().points
+    );
+}
+
+#[test]
+fn a_replay_ends_when_its_input_runs_out() {
+    let mut app = headless_app();
+    start_round(&mut app);
+    app.world.resource_mut::<Actions>().p1_movement = Vec2::Y;
+    run_frames(&mut app, frames(2.0));
+    let replay = app.world.resource::<ReplayRecording>().0.clone();
+
+    let mut playback = headless_app();
+    playback.insert_resource(GameMode::TimeAttack);
+    start_replay(&mut playback, replay);
+    assert_eq!(*playback.world.resource::<GameMode>(), GameMode::Classic);
+    run_frames(&mut playback, frames(2.0) + 2);
+
+    assert_eq!(
+        *playback.world.resource::<State<GameState>>().get(),
+        GameState::GameOver
+    );
+    // Leaving the results restores the mode that was picked before watching
+    playback
+        .world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Menu);
+    playback.update();
+    assert_eq!(*playback.world.resource::<GameMode>(), GameMode::TimeAttack);
+}
+
+#[test]
+fn time_attack_tracks_the_queen_path() {
+    let mut app = headless_app();
+    app.insert_resource(GameMode::TimeAttack);
+    start_round(&mut app);
+    app.world.resource_mut::<Actions>().p1_movement = Vec2::new(1.0, -0.2);
+    run_frames(&mut app, frames(2.0));
+
+    let time_attack = app.world.resource::<TimeAttack>();
+    assert_eq!(time_attack.steps as usize, frames(2.0));
+    let track = time_attack.track.clone();
+    assert_eq!(
+        track.samples.len(),
+        frames(2.0).div_ceil(track.sample_interval as usize)
+    );
+    // The ghost is drawn between the samples of the track
+    let end = track.position_at(frames(2.0) as f32).unwrap();
+    assert!(end.x > track.samples[0].x + 100.0);
+
+    let decoded = PositionTrack::from_bytes(&track.to_bytes()).unwrap();
+    assert_eq!(decoded.sample_interval, track.sample_interval);
+    assert_eq!(decoded.samples.len(), track.samples.len());
+    for (decoded, sample) in decoded.samples.iter().zip(&track.samples) {
+        assert!(decoded.distance(*sample) < 0.1);
+    }
+    assert!(PositionTrack::from_bytes(b"not a track").is_none());
+}
+
+#[test]
+fn the_bot_collects_flowers() {
+    let mut app = headless_app();
+    app.add_plugins(BotPlugin);
+    app.world.resource_mut::<GameRng>().fixed_seed = Some(3);
+    start_round(&mut app);
+    run_frames(&mut app, frames(20.0));
+
+    let stats = app.world.resource::<RoundStats>();
+    assert!(stats.flowers >= 2, "{stats:?}");
+    assert!(!stats.bee_curve.is_empty());
+    assert!(stats.max_bees > 0);
+}
+
+#[test]
+fn a_bot_round_plays_back_the_same() {
+    let mut app = headless_app();
+    app.add_plugins(BotPlugin);
+    start_round(&mut app);
+    run_frames(&mut app, frames(10.0));
+    let replay = app.world.resource::<ReplayRecording>().0.clone();
+
+    let mut playback = headless_app();
+    start_replay(&mut playback, replay);
+    run_frames(&mut playback, frames(10.0));
+
+    assert_eq!(queen_position(&mut playback), queen_position(&mut app));
+    assert_eq!(
+        playback.world.resource::<Score>().points,
+        app.world.resource::<Score>().points
+    );
+}
+
+#[test]
+fn launch_options_set_up_the_round() {
+    let args = [
+        "--seed",
+        "7",
+        "--difficulty",
+        "hard",
+        "--mode",
+        "time-attack",
+    ];
+    let options = LaunchOptions::parse(args.map(String::from)).unwrap();
+    let mut app = headless_app();
+    app.add_plugins(LaunchPlugin(options));
+    start_round(&mut app);
+
+    assert_eq!(app.world.resource::<GameRng>().seed, 7);
+    assert_eq!(
+        app.world.resource::<Difficulty>().preset,
+        DifficultyPreset::Hard
+    );
+    assert_eq!(*app.world.resource::<GameMode>(), GameMode::TimeAttack);
+
+    assert!(LaunchOptions::parse(["--seed".to_string()]).is_err());
+    assert!(LaunchOptions::parse(["--arena".to_string()]).is_err());
+    assert!(LaunchOptions::parse(["--help".to_string()]).unwrap().help);
+    assert!(LaunchOptions::parse(["--window-size".to_string(), "wide".to_string()]).is_err());
+}
+
+#[test]
+fn the_arena_option_loads_a_layout_file() {
+    let arena = ArenaLayout {
+        queen_spawn: Vec2::new(0.0, -200.0),
+        walls: vec![ArenaRect::new(Vec2::ZERO, Vec2::new(200.0, 20.0))],
+        ..default()
+    };
+    let path = std::env::temp_dir().join("queen_beevy_launch_test.arena.ron");
+    std::fs::write(&path, ron::to_string(&arena).unwrap()).unwrap();
+    let options =
+        LaunchOptions::parse(["--arena".to_string(), path.display().to_string()]).unwrap();
+    let mut app = headless_app();
+    app.add_plugins(LaunchPlugin(options));
+    start_round(&mut app);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(*app.world.resource::<ArenaLayout>(), arena);
+    assert_eq!(queen_position(&mut app), arena.queen_spawn);
+}
+
+#[test]
+fn the_camera_leans_towards_the_queen_near_an_edge() {
+    let mut app = headless_app();
+    app.add_plugins((CameraPlugin, InterpolationPlugin))
+        .insert_resource(UiScale(1.0));
+    start_round(&mut app);
+    let focus = |app: &mut App| {
+        app.world
+            .query::<&CameraController>()
+            .single(&app.world)
+            .focus()
+    };
+
+    // The queen starts next to the left wall
+    run_frames(&mut app, frames(1.0));
+    assert!(focus(&mut app).x < -10.0);
+
+    app.world.resource_mut::<Actions>().p1_movement = Vec2::X;
+    run_frames(&mut app, frames(3.0));
+    assert!(queen_position(&mut app).x > 200.0);
+    assert!(focus(&mut app).x > 10.0);
+}
+
+#[test]
+fn the_menu_only_offers_custom_when_it_can_differ_from_normal() {
+    let mut difficulty = Difficulty::default();
+    difficulty.select(DifficultyPreset::Hard);
+    if !cfg!(feature = "dev") {
+        assert_eq!(difficulty.next_preset(), DifficultyPreset::Easy);
+    }
+
+    let tuning: Tuning = ron::from_str("(difficulty: (custom: (substep_count: 2)))").unwrap();
+    difficulty.presets = tuning.difficulty;
+    assert_eq!(difficulty.next_preset(), DifficultyPreset::Custom);
+    let custom = tuning.difficulty.custom.unwrap();
+    assert_eq!(custom.substep_count, 2);
+    assert_eq!(
+        custom.starting_production,
+        tuning.difficulty.normal.starting_production
+    );
+}
+
+#[test]
+fn the_tuning_file_matches_the_defaults() {
+    let tuning: Tuning = ron::from_str(include_str!("../assets/tuning.ron")).unwrap();
+    assert_eq!(tuning, Tuning::default());
+}
+
+#[test]
+fn a_partial_entry_keeps_the_defaults_of_its_own_preset_and_kind() {
+    let tuning: Tuning = ron::from_str(
+        "(difficulty: (hard: (substep_count: 2)), flowers: (orchid: (points: 20.0)))",
+    )
+    .unwrap();
+    let defaults = Tuning::default();
+    assert_eq!(tuning.difficulty.hard.substep_count, 2);
+    assert_eq!(
+        tuning.difficulty.hard.starting_production,
+        defaults.difficulty.hard.starting_production
+    );
+    assert_eq!(
+        tuning.difficulty.hard.queen_movement_scaling,
+        defaults.difficulty.hard.queen_movement_scaling
+    );
+    assert_eq!(tuning.difficulty.easy, defaults.difficulty.easy);
+
+    assert_eq!(tuning.flowers.orchid.points, 20.0);
+    assert_eq!(
+        tuning.flowers.orchid.lifetime,
+        defaults.flowers.orchid.lifetime
+    );
+    assert_eq!(tuning.flowers.orchid.weight, defaults.flowers.orchid.weight);
+    assert_eq!(tuning.flowers.sunflower, defaults.flowers.sunflower);
+}
+
+#[test]
+fn the_arena_layout_places_the_round() {
+    let zone = ArenaRect::new(Vec2::new(-250.0, -180.0), Vec2::new(60.0, 40.0));
+    let mut app = headless_app();
+    app.insert_resource(ArenaLayout {
+        queen_spawn: Vec2::new(100.0, 100.0),
+        first_flower: Vec2::new(-200.0, 0.0),
+        flower_zones: vec![zone],
+        ..default()
+    });
+    start_round(&mut app);
+
+    assert_eq!(queen_position(&mut app), Vec2::new(100.0, 100.0));
+    let (daisy, _, position) = flowers(&mut app)[0];
+    assert_eq!(position, Vec2::new(-200.0, 0.0));
+
+    run_frames(&mut app, frames(9.0));
+    let grown: Vec<Vec2> = flowers(&mut app)
+        .into_iter()
+        .filter(|(entity, _, _)| *entity != daisy)
+        .map(|(_, _, position)| position)
+        .collect();
+    assert!(!grown.is_empty());
+    assert!(grown.iter().all(|position| zone.contains(*position)));
+}
+
+#[test]
+fn a_queen_that_stays_on_a_hazard_keeps_losing_lives() {
+    let mut app = headless_app();
+    let spawn = ArenaLayout::default().queen_spawn;
+    app.insert_resource(ArenaLayout {
+        hazards: vec![ArenaRect::new(spawn, Vec2::splat(40.0))],
+        ..default()
+    });
+    start_round(&mut app);
+
+    // Every hit sends her back onto the hazard, so each life only lasts the invulnerability
+    let over = run_until(&mut app, frames(5.0), |world| {
+        *world.resource::<State<GameState>>().get() == GameState::GameOver
+    });
+    assert!(over);
+}
+
+#[test]
+fn the_arena_file_matches_the_defaults() {
+    let layout: ArenaLayout = ron::from_str(include_str!("../assets/arena.arena.ron")).unwrap();
+    assert_eq!(layout, ArenaLayout::default());
+}